@@ -5,6 +5,15 @@ mod listener;
 mod telemetry_session;
 mod auth;
 mod request;
+mod records;
+mod tracks;
+mod settings;
+mod tyre_sets;
+mod weekend;
+mod results;
+mod power_unit;
+mod setups;
+mod overlay;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -16,7 +25,7 @@ pub fn run() {
         .plugin(tauri_plugin_sql::Builder::default().build())
         .plugin(tauri_plugin_store::Builder::default().build())
         .plugin(tauri_plugin_opener::init())
-        .invoke_handler(tauri::generate_handler![auth::authenticate, listener::listen_for_telemetry])
+        .invoke_handler(tauri::generate_handler![auth::authenticate, listener::listen_for_telemetry, records::get_lap_records, tracks::get_segment_map, tracks::get_track_limits, settings::get_settings, settings::save_settings, tyre_sets::get_tyre_inventory, weekend::get_weekend, weekend::get_championship, results::get_session_results, power_unit::get_power_unit_history, power_unit::get_power_unit_projections, setups::get_setup_reports, overlay::toggle_overlay])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -1,10 +1,10 @@
 use std::sync::Arc;
 
-use tauri::Wry;
+use tauri::{AppHandle, Wry};
 use tauri_plugin_store::{Store, StoreExt};
 use tokio::net::{ToSocketAddrs, UdpSocket};
 use telemetry::{session::Session as TelemetrySession, EventDataDetails, FromBytes, Packet};
-use crate::telemetry_session::{self, PacketHandler};
+use crate::{settings, telemetry_session::{self, PacketHandler}};
 
 use log::{debug, error};
 
@@ -12,20 +12,21 @@ use log::{debug, error};
 pub async fn listen_for_telemetry(app_handle: tauri::AppHandle, addr: String) -> Result<(), String> {
     let store = app_handle.store("credentials.json").map_err(|err| err.to_string())?;
 
-    let mut listener = UDPListener::new(&addr, &store).await?;
+    let mut listener = UDPListener::new(&addr, &store, app_handle.clone()).await?;
     listener.listen().await
 }
 
 pub struct UDPListener<'a> {
     pub store: &'a Arc<Store<Wry>>,
     pub socket: UdpSocket,
+    pub app_handle: AppHandle,
     pub current_session: Option<TelemetrySession>
 }
 
 impl<'a> UDPListener<'a> {
-    pub async fn new<T: ToSocketAddrs>(addr: T, store: &'a Arc<Store<Wry>>) -> Result<Self, String> {
+    pub async fn new<T: ToSocketAddrs>(addr: T, store: &'a Arc<Store<Wry>>, app_handle: AppHandle) -> Result<Self, String> {
         match UdpSocket::bind(addr).await.map_err(|err| err.to_string()) {
-            Ok(socket) => Ok(Self { socket, current_session: None, store }),
+            Ok(socket) => Ok(Self { socket, app_handle, current_session: None, store }),
             Err(err) => Err(err)
         }
     }
@@ -41,7 +42,7 @@ impl<'a> UDPListener<'a> {
 
                     match &mut self.current_session {
                         Some(s) => {
-                            s.handle_packet(packet, self.store).await;
+                            s.handle_packet(packet, self.store, &self.app_handle).await;
                         },
                         _ => {}
                     }
@@ -57,7 +58,11 @@ impl<'a> UDPListener<'a> {
         match packet {
             Packet::Event(p) => {
                 match p.event_details {
-                    EventDataDetails::SessionStarted => self.current_session = Some(TelemetrySession::new(p.header)),
+                    EventDataDetails::SessionStarted => {
+                        let mut session = TelemetrySession::new(p.header);
+                        session.fuel.seconds_per_kg = settings::load_settings(&self.app_handle).fuel_seconds_per_kg;
+                        self.current_session = Some(session);
+                    },
                     EventDataDetails::SessionEnded => {
                         if self.current_session.is_none() {
                             return;
@@ -0,0 +1,13 @@
+use tauri::{AppHandle, Manager};
+
+/// Label of the always-on-top window showing the live delta over the game.
+const OVERLAY_WINDOW: &str = "overlay";
+
+/// Shows or hides the overlay. Returns true if it is now shown.
+#[tauri::command]
+pub fn toggle_overlay(app_handle: AppHandle) -> Result<bool, String> {
+    let window = app_handle.get_webview_window(OVERLAY_WINDOW).ok_or("Overlay window not found")?;
+    let visible = window.is_visible().map_err(|err| err.to_string())?;
+    if visible { window.hide() } else { window.show() }.map_err(|err| err.to_string())?;
+    Ok(!visible)
+}
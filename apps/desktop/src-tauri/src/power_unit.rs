@@ -0,0 +1,42 @@
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+use telemetry::{power_unit::{PowerUnitHistory, WearProjection, DEFAULT_WEAR_LIMIT}, session::Session, weekend::WeekendLink};
+
+/// Store holding the player's power unit wear, keyed by season link identifier.
+const POWER_UNIT_STORE: &str = "power_unit.json";
+
+fn load(app_handle: &AppHandle, season_link_identifier: u32) -> Option<PowerUnitHistory> {
+    let store = app_handle.store(POWER_UNIT_STORE).ok()?;
+    serde_json::from_value(store.get(season_link_identifier.to_string())?).ok()
+}
+
+pub fn save_history(app_handle: &AppHandle, history: &PowerUnitHistory) -> Result<(), String> {
+    let store = app_handle.store(POWER_UNIT_STORE).map_err(|err| err.to_string())?;
+    let value = serde_json::to_value(history).map_err(|err| err.to_string())?;
+
+    store.set(history.season_link_identifier.to_string(), value);
+    store.save().map_err(|err| err.to_string())
+}
+
+/// Loads the season's power unit history whenever the player moves on to a new season.
+pub fn sync_history(session: &mut Session, app_handle: &AppHandle, link: WeekendLink) {
+    if session.power_unit.as_ref().is_some_and(|history| history.season_link_identifier == link.season_link_identifier) {
+        return;
+    }
+    session.power_unit = Some(
+        load(app_handle, link.season_link_identifier).unwrap_or_else(|| PowerUnitHistory::new(link.season_link_identifier)),
+    );
+}
+
+#[tauri::command]
+pub fn get_power_unit_history(app_handle: AppHandle, season_link_identifier: u32) -> Option<PowerUnitHistory> {
+    load(&app_handle, season_link_identifier)
+}
+
+/// When each component is expected to reach the wear limit.
+#[tauri::command]
+pub fn get_power_unit_projections(app_handle: AppHandle, season_link_identifier: u32) -> Vec<WearProjection> {
+    load(&app_handle, season_link_identifier)
+        .map(|history| history.projections(DEFAULT_WEAR_LIMIT))
+        .unwrap_or_default()
+}
@@ -0,0 +1,61 @@
+use log::error;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+use telemetry::{delta::ReferenceLap, records::LapRecords, session::Session};
+
+/// Store holding the player's reference laps, keyed by track and assist mask.
+const RECORDS_STORE: &str = "records.json";
+/// Store holding the player's best lap and sector times, keyed by track and assist mask.
+const SECTOR_RECORDS_STORE: &str = "sector_records.json";
+
+fn load<T: serde::de::DeserializeOwned>(app_handle: &AppHandle, store: &str, key: &str) -> Option<T> {
+    let store = app_handle.store(store).ok()?;
+    serde_json::from_value(store.get(key)?).ok()
+}
+
+fn save<T: serde::Serialize>(app_handle: &AppHandle, store: &str, key: String, value: &T) -> Result<(), String> {
+    let store = app_handle.store(store).map_err(|err| err.to_string())?;
+    let value = serde_json::to_value(value).map_err(|err| err.to_string())?;
+
+    store.set(key, value);
+    store.save().map_err(|err| err.to_string())
+}
+
+pub fn save_reference_lap(app_handle: &AppHandle, reference: &ReferenceLap) -> Result<(), String> {
+    save(app_handle, RECORDS_STORE, ReferenceLap::key(reference.track_id, reference.assists), reference)
+}
+
+pub fn save_track_records(app_handle: &AppHandle, key: &str, records: &LapRecords) -> Result<(), String> {
+    save(app_handle, SECTOR_RECORDS_STORE, key.to_string(), records)
+}
+
+/// Makes sure the session's reference lap and track records match its current track
+/// and assists, loading them from the store whenever either of them changes.
+pub fn sync_records(session: &mut Session, app_handle: &AppHandle) {
+    let key = session.records_key();
+    if key == session.records_key {
+        return;
+    }
+
+    session.reference_lap = key.as_deref().and_then(|key| load(app_handle, RECORDS_STORE, key));
+    session.track_records = key.as_deref().and_then(|key| load(app_handle, SECTOR_RECORDS_STORE, key)).unwrap_or_default();
+    session.records_key = key;
+    measure_reference_braking(session, app_handle);
+}
+
+/// Measures the reference lap's braking once the track's corners are known and saves it again.
+pub fn measure_reference_braking(session: &mut Session, app_handle: &AppHandle) {
+    if !session.measure_reference_braking() {
+        return;
+    }
+    if let Some(reference) = &session.reference_lap {
+        if let Err(e) = save_reference_lap(app_handle, reference) {
+            error!("Failed to save reference lap: {}", e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_lap_records(app_handle: AppHandle, track_id: i8, assists: u16) -> Option<LapRecords> {
+    load(&app_handle, SECTOR_RECORDS_STORE, &ReferenceLap::key(track_id, assists))
+}
@@ -4,12 +4,13 @@ use log::info;
 use serde::{Deserialize, Serialize};
 use tauri::Wry;
 use tauri_plugin_store::Store;
-use telemetry::{session::Lap, JSONCarTelemetryData};
+use telemetry::{analysis::{braking_metrics, compare_braking, excursions, traction_and_balance, BrakingComparison, CornerBraking, Excursion, LapBalance, LapSegment, SegmentMap}, ers::ErsUsage, fuel::{FuelConsumption, FuelUsage}, lap_time::{LapTime, SectorTime}, pit_stops::PitStop, records::LapRecords, session::{Lap, Session}, tyres::{DegradationRate, TyreLap, TyreStint}, weather::{ForecastAccuracy, TrackConditions, WeatherSample}, classification::Classification, damage::{Collision, DamageState}, setup::{CarSetup, SetupSnapshot}, incidents::{Incident, LapInvalidation}, JSONCarTelemetryData};
 
 #[derive(Debug)]
 pub enum RequestError {
     ReqwestError(reqwest::Error),
     HttpError(reqwest::StatusCode),
+    MissingAccessToken,
 }
 
 impl std::fmt::Display for RequestError {
@@ -17,6 +18,7 @@ impl std::fmt::Display for RequestError {
         match self {
             RequestError::ReqwestError(e) => write!(f, "Reqwest error: {:#?}", e),
             RequestError::HttpError(e) => write!(f, "HTTP error: {:#?}", e),
+            RequestError::MissingAccessToken => write!(f, "No access token in store"),
         }
     }
 }
@@ -27,6 +29,25 @@ pub struct ApiSessionResponse {
     pub session_uid: String,
 }
 
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ApiWeekendResponse {
+    pub status: String,
+    pub weekend_uid: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ApiSegmentMapResponse {
+    pub status: String,
+    pub segment_map: Option<SegmentMap>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ApiSegmentMapUploadResponse {
+    pub status: String,
+    /// False when the registry already held a map for the track
+    pub updated: bool,
+}
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct ApiLapResponse {
     status: String,
@@ -36,27 +57,64 @@ pub struct ApiLapResponse {
 #[serde(rename_all = "camelCase")]
 pub struct ApiLapRequest {
     pub lap_number: u8,
-    pub lap_time_in_ms: u32,
-    pub sector1_time_in_ms: u16,
-    pub sector2_time_in_ms: u16,
-    pub sector3_time_in_ms: u16,
+    pub lap_time_in_ms: LapTime,
+    pub sector1_time_in_ms: SectorTime,
+    pub sector2_time_in_ms: SectorTime,
+    pub sector3_time_in_ms: SectorTime,
     pub lap_invalid: bool,
     pub assists: u16,
     pub total_distance: f32,
-    pub car_telemetry: BTreeMap<u32, JSONCarTelemetryData>
+    pub car_telemetry: BTreeMap<u32, JSONCarTelemetryData>,
+    pub segments: Vec<LapSegment>,
+    pub braking: Vec<CornerBraking>,
+    pub braking_comparison: Vec<BrakingComparison>,
+    pub balance: Option<LapBalance>,
+    pub tyres: Option<TyreLap>,
+    pub tyre_laps_remaining: Option<f32>,
+    pub fuel: Option<FuelUsage>,
+    pub fuel_corrected_lap_time_in_ms: Option<LapTime>,
+    pub ers: Option<ErsUsage>,
+    pub ers_energy_balance: Option<f32>,
+    pub conditions: Option<TrackConditions>,
+    pub incidents: Vec<Incident>,
+    pub invalidations: Vec<LapInvalidation>,
+    pub excursions: Vec<Excursion>,
+    pub damage: Option<DamageState>,
+    pub setup: Option<CarSetup>,
 }
 
 impl ApiLapRequest {
     // we send the total distance of the session every lap, to make sure it is up to date on the server.
-    pub fn new(lap: Lap) -> Self {
+    pub fn new(lap: Lap, session: &Session) -> Self {
         info!("Creating new lap request");
+        let braking = session.segment_map.as_ref().map(|map| braking_metrics(&lap, map)).unwrap_or_default();
+        let braking_comparison = session.reference_lap.as_ref()
+            .map(|reference| compare_braking(&braking, &reference.braking))
+            .unwrap_or_default();
+
         Self {
+            segments: session.segment_map.as_ref().map(|map| map.split_lap(&lap)).unwrap_or_default(),
+            braking,
+            braking_comparison,
+            balance: session.segment_map.as_ref().and_then(|map| traction_and_balance(&lap, map)),
+            tyres: session.tyres.lap(lap.lap_number + 1).copied(),
+            tyre_laps_remaining: session.tyre_laps_remaining(),
+            fuel: lap.fuel,
+            fuel_corrected_lap_time_in_ms: session.fuel.corrected_lap_time(&lap),
+            ers_energy_balance: lap.ers.as_ref().map(|ers| ers.energy_balance()),
+            ers: lap.ers.clone(),
+            conditions: lap.conditions,
+            incidents: lap.incidents.clone(),
+            invalidations: lap.invalidations.clone(),
+            damage: lap.damage,
+            setup: lap.setup,
+            excursions: excursions(&lap, session.segment_map.as_ref(), session.reference_lap.as_ref()),
             lap_number: lap.lap_number + 1,
             total_distance: lap.total_distance,
-            lap_time_in_ms: lap.lap_time_in_ms,
-            sector1_time_in_ms: lap.sector1_time_in_ms,
-            sector2_time_in_ms: lap.sector2_time_in_ms,
-            sector3_time_in_ms: (lap.lap_time_in_ms - (lap.sector1_time_in_ms as u32 + lap.sector2_time_in_ms as u32)) as u16,
+            lap_time_in_ms: lap.lap_time,
+            sector1_time_in_ms: lap.sector1_time,
+            sector2_time_in_ms: lap.sector2_time,
+            sector3_time_in_ms: lap.sector3_time().unwrap_or_default(),
             lap_invalid: lap.lap_invalid,
             assists: lap.assists.unwrap().get_mask().unwrap(),
             car_telemetry: lap.car_telemetry
@@ -64,7 +122,61 @@ impl ApiLapRequest {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiSessionEndRequest {
+    pub total_laps: Option<u8>,
+    pub end_date: chrono::DateTime<chrono::Utc>,
+    pub total_distance: Option<f32>,
+    pub session_records: LapRecords,
+    pub track_records: LapRecords,
+    pub tyre_stints: Vec<TyreStint>,
+    pub tyre_degradation: Vec<DegradationRate>,
+    pub fuel_consumption: Vec<FuelConsumption>,
+    pub pit_stops: Vec<PitStop>,
+    pub weather_timeline: Vec<WeatherSample>,
+    pub forecast_accuracy: Vec<ForecastAccuracy>,
+    pub classification: Option<Classification>,
+    pub incidents: Vec<Incident>,
+    /// Every lap deleted by the game, including laps already uploaded when the penalty came in
+    pub lap_invalidations: Vec<LapInvalidation>,
+    pub collisions: Vec<Collision>,
+    pub setups: Vec<SetupSnapshot>,
+}
+
+impl ApiSessionEndRequest {
+    pub fn new(session: &Session, end_date: chrono::DateTime<chrono::Utc>) -> Self {
+        Self {
+            total_laps: session.total_laps,
+            end_date,
+            total_distance: session.total_distance,
+            session_records: session.session_records,
+            track_records: session.track_records,
+            tyre_stints: session.tyres.stints.clone(),
+            tyre_degradation: session.tyres.degradation_rates(),
+            fuel_consumption: session.fuel.consumption.clone(),
+            pit_stops: session.pit_stops.stops.clone(),
+            weather_timeline: session.weather_timeline.samples.clone(),
+            forecast_accuracy: session.weather_timeline.accuracy.clone(),
+            classification: session.classification.clone(),
+            incidents: session.incidents.incidents.clone(),
+            lap_invalidations: session.lap_invalidations.clone(),
+            collisions: session.collision_detector.collisions.clone(),
+            setups: session.setups.snapshots.clone(),
+        }
+    }
+}
+
+pub fn access_token(store: &Arc<Store<Wry>>) -> Result<String, RequestError> {
+    store.get("access_token")
+        .and_then(|raw_token| serde_json::from_value(raw_token).ok())
+        .ok_or(RequestError::MissingAccessToken)
+}
+
 pub trait RequestHandler {
     async fn post_new_session(&self, store: &Arc<Store<Wry>>) -> Result<ApiSessionResponse, RequestError>;
     async fn post_new_lap(&self, lap: &Lap, store: &Arc<Store<Wry>>) -> Result<ApiLapResponse, RequestError>;
+    async fn post_new_weekend(&self, store: &Arc<Store<Wry>>) -> Result<ApiWeekendResponse, RequestError>;
+    async fn get_segment_map(&self, track_id: i8) -> Result<Option<SegmentMap>, RequestError>;
+    async fn put_segment_map(&self, store: &Arc<Store<Wry>>) -> Result<ApiSegmentMapUploadResponse, RequestError>;
 }
\ No newline at end of file
@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+use telemetry::{classification::Classification, session::Session, session_info::SessionType};
+
+/// Store holding the final classification of every finished session, keyed by start date.
+const RESULTS_STORE: &str = "results.json";
+
+/// A finished session's results as kept on disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionResult {
+    pub session_uid: Option<String>,
+    pub start_date: chrono::DateTime<chrono::Utc>,
+    pub track_id: Option<i8>,
+    pub session_type: Option<SessionType>,
+    pub player_car_index: u8,
+    pub classification: Classification,
+}
+
+pub fn save_result(app_handle: &AppHandle, session: &Session) -> Result<(), String> {
+    let Some(classification) = &session.classification else {
+        return Ok(());
+    };
+    let result = SessionResult {
+        session_uid: session.session_uid.clone(),
+        start_date: session.start_date,
+        track_id: session.track_id,
+        session_type: session.info.and_then(|info| info.session_type),
+        player_car_index: session.player_car_index,
+        classification: classification.clone(),
+    };
+
+    let store = app_handle.store(RESULTS_STORE).map_err(|err| err.to_string())?;
+    let value = serde_json::to_value(&result).map_err(|err| err.to_string())?;
+
+    store.set(session.start_date.to_rfc3339(), value);
+    store.save().map_err(|err| err.to_string())
+}
+
+/// Results of every finished session, most recent first.
+#[tauri::command]
+pub fn get_session_results(app_handle: AppHandle) -> Result<Vec<SessionResult>, String> {
+    let store = app_handle.store(RESULTS_STORE).map_err(|err| err.to_string())?;
+    let mut results: Vec<SessionResult> = store
+        .values()
+        .into_iter()
+        .filter_map(|value| serde_json::from_value(value).ok())
+        .collect();
+    results.sort_by_key(|result| std::cmp::Reverse(result.start_date));
+    Ok(results)
+}
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+use telemetry::fuel::DEFAULT_SECONDS_PER_KG;
+
+/// Store holding the user's analysis settings.
+const SETTINGS_STORE: &str = "settings.json";
+const SETTINGS_KEY: &str = "settings";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    /// Lap time (s) each kilogram of fuel costs, used for fuel-corrected lap times
+    pub fuel_seconds_per_kg: f32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self { fuel_seconds_per_kg: DEFAULT_SECONDS_PER_KG }
+    }
+}
+
+fn load(app_handle: &AppHandle) -> Option<Settings> {
+    let store = app_handle.store(SETTINGS_STORE).ok()?;
+    serde_json::from_value(store.get(SETTINGS_KEY)?).ok()
+}
+
+/// Saved settings, falling back to the defaults for anything that has not been set.
+pub fn load_settings(app_handle: &AppHandle) -> Settings {
+    load(app_handle).unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn get_settings(app_handle: AppHandle) -> Settings {
+    load_settings(&app_handle)
+}
+
+#[tauri::command]
+pub fn save_settings(app_handle: AppHandle, settings: Settings) -> Result<(), String> {
+    let store = app_handle.store(SETTINGS_STORE).map_err(|err| err.to_string())?;
+    let value = serde_json::to_value(settings).map_err(|err| err.to_string())?;
+
+    store.set(SETTINGS_KEY, value);
+    store.save().map_err(|err| err.to_string())
+}
@@ -0,0 +1,37 @@
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+use telemetry::analysis::{setup_reports, SetupLap, SetupReport};
+
+/// Store holding laps summarised for setup comparison, keyed by track ID.
+const SETUP_LAPS_STORE: &str = "setup_laps.json";
+/// Laps kept per track. The oldest are dropped first.
+const MAX_SETUP_LAPS: usize = 500;
+
+fn load(app_handle: &AppHandle, track_id: i8) -> Vec<SetupLap> {
+    app_handle
+        .store(SETUP_LAPS_STORE)
+        .ok()
+        .and_then(|store| store.get(track_id.to_string()))
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_setup_lap(app_handle: &AppHandle, setup_lap: &SetupLap) -> Result<(), String> {
+    let mut laps = load(app_handle, setup_lap.track_id);
+    laps.push(*setup_lap);
+    if laps.len() > MAX_SETUP_LAPS {
+        laps.drain(..laps.len() - MAX_SETUP_LAPS);
+    }
+
+    let store = app_handle.store(SETUP_LAPS_STORE).map_err(|err| err.to_string())?;
+    let value = serde_json::to_value(&laps).map_err(|err| err.to_string())?;
+
+    store.set(setup_lap.track_id.to_string(), value);
+    store.save().map_err(|err| err.to_string())
+}
+
+/// Setups the player has run at the track, ranked by pace in each set of conditions.
+#[tauri::command]
+pub fn get_setup_reports(app_handle: AppHandle, track_id: i8) -> Vec<SetupReport> {
+    setup_reports(&load(&app_handle, track_id))
+}
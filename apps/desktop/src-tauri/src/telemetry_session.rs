@@ -1,15 +1,15 @@
 use std::sync::Arc;
 use log::{error, info};
 use reqwest::StatusCode;
-use serde_json::json;
-use tauri::Wry;
+use tauri::{AppHandle, Emitter, Wry};
 use tauri_plugin_store::Store;
-use telemetry::{assists::Assists, session::{JSONTelemetrySession, Lap, Session}, JSONCarTelemetryData, Packet};
+use telemetry::{analysis::SegmentMap, assists::Assists, classification::{Classification, Participant}, damage::DamageState, setup::CarSetup, session::{JSONTelemetrySession, Lap, Session}, session_info::SessionInfo, strategy::PitWindow, weekend::{EngineWear, WeekendLink}, JSONCarMotionData, JSONCarTelemetryData, MotionExData, Packet};
 
-use crate::request::{ApiLapRequest, ApiLapResponse, ApiSessionResponse, RequestError, RequestHandler};
+use crate::{power_unit, records, results, setups, tracks, tyre_sets, weekend};
+use crate::request::{access_token, ApiLapRequest, ApiLapResponse, ApiSegmentMapResponse, ApiSegmentMapUploadResponse, ApiSessionEndRequest, ApiSessionResponse, ApiWeekendResponse, RequestError, RequestHandler};
 
 pub trait PacketHandler {
-    async fn handle_packet(&mut self, packet: Packet, store: &Arc<Store<Wry>>) -> ();
+    async fn handle_packet(&mut self, packet: Packet, store: &Arc<Store<Wry>>, app_handle: &AppHandle) -> ();
 }
 
 pub async fn end_session(session: &mut Session, store: &Arc<Store<Wry>>) -> Result<(), RequestError> {
@@ -32,11 +32,7 @@ pub async fn end_session(session: &mut Session, store: &Arc<Store<Wry>>) -> Resu
 
         let res = client.put(url)
             .bearer_auth(access_token)
-            .json(&json!({
-                "totalLaps": session.total_laps,
-                "endDate": end_date,
-                "totalDistance": session.total_distance,
-            }))
+            .json(&ApiSessionEndRequest::new(session, end_date))
             .send()
             .await;
         match res {
@@ -62,6 +58,50 @@ pub async fn end_session(session: &mut Session, store: &Arc<Store<Wry>>) -> Resu
     Ok(())
 }
 
+/// Loads the corner map for a track, falling back to the one stored in the track registry.
+async fn load_segment_map(session: &mut Session, app_handle: &AppHandle, track_id: i8) {
+    session.segment_map = tracks::load_segment_map(app_handle, track_id);
+    if session.segment_map.is_none() {
+        match session.get_segment_map(track_id).await {
+            Ok(Some(segment_map)) => {
+                info!("Loaded segment map for track {} from the track registry", track_id);
+                if let Err(e) = tracks::save_segment_map(app_handle, &segment_map) {
+                    error!("Failed to save segment map: {}", e);
+                }
+                session.segment_map = Some(segment_map);
+            },
+            Ok(None) => {},
+            Err(e) => error!("Failed to fetch segment map: {}", e),
+        }
+    }
+    records::measure_reference_braking(session, app_handle);
+}
+
+/// Stores a freshly detected corner map locally and in the track registry. If the registry
+/// already holds a map for the track, that one is adopted so corner numbers match the backend.
+async fn upload_segment_map(session: &mut Session, store: &Arc<Store<Wry>>, app_handle: &AppHandle) {
+    let Some(track_id) = session.segment_map.as_ref().map(|map| map.track_id) else {
+        return;
+    };
+
+    match session.put_segment_map(store).await {
+        Ok(res) if !res.updated => match session.get_segment_map(track_id).await {
+            Ok(Some(segment_map)) => session.segment_map = Some(segment_map),
+            Ok(None) => {},
+            Err(e) => error!("Failed to fetch segment map: {}", e),
+        },
+        Ok(_) => info!("Uploaded segment map for track {}", track_id),
+        Err(e) => error!("Failed to upload segment map: {}", e),
+    }
+
+    if let Some(segment_map) = &session.segment_map {
+        if let Err(e) = tracks::save_segment_map(app_handle, segment_map) {
+            error!("Failed to save segment map: {}", e);
+        }
+    }
+    records::measure_reference_braking(session, app_handle);
+}
+
 impl RequestHandler for Session {
     async fn post_new_session(&self, store: &Arc<Store<Wry>>) -> Result<ApiSessionResponse, RequestError> {
         let client = reqwest::Client::new();
@@ -106,7 +146,7 @@ impl RequestHandler for Session {
 
                 let raw_token = store.get("access_token").expect("Failed to get value from store");
                 let access_token: String = serde_json::from_value(raw_token).unwrap();
-                let payload = ApiLapRequest::new(lap.clone());
+                let payload = ApiLapRequest::new(lap.clone(), self);
 
                 let res = client.post(url)
                     .bearer_auth(access_token)
@@ -135,10 +175,64 @@ impl RequestHandler for Session {
             }
         }
     }
+
+    async fn post_new_weekend(&self, store: &Arc<Store<Wry>>) -> Result<ApiWeekendResponse, RequestError> {
+        let Some(weekend) = &self.weekend else {
+            return Err(RequestError::HttpError(StatusCode::BAD_REQUEST));
+        };
+        let client = reqwest::Client::new();
+        let url = "http://localhost:5173/api/weekend";
+        let access_token = access_token(store)?;
+
+        let res = client.post(url)
+            .bearer_auth(access_token)
+            .json(weekend)
+            .send()
+            .await
+            .map_err(RequestError::ReqwestError)?;
+        match res.status() {
+            StatusCode::OK => res.json::<ApiWeekendResponse>().await.map_err(RequestError::ReqwestError),
+            status => Err(RequestError::HttpError(status)),
+        }
+    }
+
+    async fn get_segment_map(&self, track_id: i8) -> Result<Option<SegmentMap>, RequestError> {
+        let client = reqwest::Client::new();
+        let url = format!("http://localhost:5173/api/track/{}/segments", track_id);
+
+        let res = client.get(url).send().await.map_err(RequestError::ReqwestError)?;
+        match res.status() {
+            StatusCode::OK => {
+                let body = res.json::<ApiSegmentMapResponse>().await.map_err(RequestError::ReqwestError)?;
+                Ok(body.segment_map)
+            },
+            status => Err(RequestError::HttpError(status)),
+        }
+    }
+
+    async fn put_segment_map(&self, store: &Arc<Store<Wry>>) -> Result<ApiSegmentMapUploadResponse, RequestError> {
+        let Some(segment_map) = &self.segment_map else {
+            return Err(RequestError::HttpError(StatusCode::BAD_REQUEST));
+        };
+        let client = reqwest::Client::new();
+        let url = format!("http://localhost:5173/api/track/{}/segments", segment_map.track_id);
+        let access_token = access_token(store)?;
+
+        let res = client.put(url)
+            .bearer_auth(access_token)
+            .json(segment_map)
+            .send()
+            .await
+            .map_err(RequestError::ReqwestError)?;
+        match res.status() {
+            StatusCode::OK => res.json::<ApiSegmentMapUploadResponse>().await.map_err(RequestError::ReqwestError),
+            status => Err(RequestError::HttpError(status)),
+        }
+    }
 }
 
 impl PacketHandler for Session where Session: RequestHandler {
-    async fn handle_packet(&mut self, packet: telemetry::Packet, store: &Arc<Store<Wry>>) -> () {
+    async fn handle_packet(&mut self, packet: telemetry::Packet, store: &Arc<Store<Wry>>, app_handle: &AppHandle) -> () {
         match packet {
             Packet::Session(p) => {
                 if self.is_initialised() && self.session_uid.is_none() {
@@ -146,15 +240,57 @@ impl PacketHandler for Session where Session: RequestHandler {
                     match self.post_new_session(store).await {
                         Ok(res) => {        
                             info!("Created new telemetry session on backend");       
+                            if let (Some(link), Some(weekend)) = (self.link, &mut self.weekend) {
+                                weekend.set_session_uid(link.session_link_identifier, &res.session_uid);
+                                if let Err(e) = weekend::save_weekend(app_handle, weekend) {
+                                    error!("Failed to save weekend: {}", e);
+                                }
+                            }
                             self.session_uid = Some(res.session_uid);
                         },
                         Err(err) => { error!("Error creating new backend session: ${:#?}", err) }
                     }
                 } else {
                     self.weather = Some(p.weather);
+                    self.track_temperature = Some(p.track_temperature);
+                    self.air_temperature = Some(p.air_temperature);
+                    if let Some(lap) = &mut self.current_lap {
+                        lap.record_conditions(&p);
+                    }
+                    if let Some(alert) = self.weather_timeline.update(&p) {
+                        info!("Weather alert: {:?}", alert.call);
+                        if let Err(e) = app_handle.emit("weather-alert", alert) {
+                            error!("{:#?}", e);
+                        }
+                    }
                     self.time_of_day = Some(p.time_of_day);
                     self.total_laps = Some(p.total_laps);
+                    if self.track_id != Some(p.track_id) {
+                        load_segment_map(self, app_handle, p.track_id).await;
+                        self.pit_loss = tracks::load_pit_loss(app_handle, p.track_id);
+                        self.track_limits = tracks::load_track_limits(app_handle, p.track_id);
+                    }
                     self.track_id = Some(p.track_id);
+                    self.track_length = Some(p.track_length);
+                    self.info = Some(SessionInfo::from_session(&p));
+                    power_unit::sync_history(self, app_handle, WeekendLink::from_session(&p));
+                    match weekend::sync_weekend(self, app_handle, WeekendLink::from_session(&p), p.track_id) {
+                        Ok(true) => match self.post_new_weekend(store).await {
+                            Ok(res) => {
+                                info!("Created new weekend on backend");
+                                if let Some(weekend) = &mut self.weekend {
+                                    weekend.weekend_uid = Some(res.weekend_uid);
+                                    if let Err(e) = weekend::save_weekend(app_handle, weekend) {
+                                        error!("Failed to save weekend: {}", e);
+                                    }
+                                }
+                            },
+                            Err(e) => error!("Error creating new backend weekend: {:#?}", e),
+                        },
+                        Ok(false) => {},
+                        Err(e) => error!("Failed to save weekend: {}", e),
+                    }
+                    self.pit_window = Some(PitWindow::from_session(&p));
                     match &mut self.assists {
                         None => self.assists = Some(Assists::from_session(p)),
                         Some(stored_assists) => {
@@ -176,7 +312,13 @@ impl PacketHandler for Session where Session: RequestHandler {
                 }
             }
             Packet::CarStatus(p) => {
+                self.field.update_status(&p);
                 let car_status_data = p.car_status_data[self.player_car_index as usize];
+                self.tyres.update_status(&car_status_data);
+                if let Some(lap) = &mut self.current_lap {
+                    lap.record_ers(&car_status_data);
+                    lap.record_fuel(&car_status_data);
+                }
                 match &mut self.assists {
                     Some(stored_assists) => {
                         stored_assists.anti_lock_brakes = Some(car_status_data.anti_lock_brakes);
@@ -195,48 +337,208 @@ impl PacketHandler for Session where Session: RequestHandler {
             Packet::Lap(p) => {
                 let lap_data = p.lap_data[self.player_car_index as usize];
                 self.total_distance = Some(lap_data.total_distance);
+                self.field.update_laps(&p);
+                if let (Some(weekend), Some(session_type)) = (&mut self.weekend, self.info.and_then(|info| info.session_type)) {
+                    weekend.record_position(session_type, &lap_data);
+                }
+                if self.update_pit_stops(&p) {
+                    if let (Some(track_id), Some(pit_loss)) = (self.track_id, &self.pit_loss) {
+                        if let Err(e) = tracks::save_pit_loss(app_handle, track_id, pit_loss) {
+                            error!("Failed to save pit loss: {}", e);
+                        }
+                    }
+                }
+                records::sync_records(self, app_handle);
             
                 match &mut self.current_lap {
                     Some(lap) => {
                         if lap.lap_number == lap_data.current_lap_num - 1 {
-                            lap.lap_time_in_ms = lap_data.current_lap_time_in_ms;
-                            lap.sector1_time_in_ms = lap_data.sector1_time_in_ms;
-                            lap.sector2_time_in_ms = lap_data.sector2_time_in_ms;
+                            lap.lap_time = lap_data.current_lap_time();
+                            lap.sector1_time = lap_data.sector1_time();
+                            lap.sector2_time = lap_data.sector2_time();
                             lap.driver_status = lap_data.driver_status;
                             lap.lap_invalid = lap_data.current_lap_invalid;
+                            lap.record_distance(&lap_data);
                         } else if lap.lap_number < lap_data.current_lap_num - 1 {
-                            lap.lap_time_in_ms = lap_data.last_lap_time_in_ms;
+                            lap.lap_time = lap_data.last_lap_time();
             
                             let finished_lap = self.current_lap.take().unwrap();
+                            let tyre_lap = self.tyres.finish_lap(finished_lap.lap_number + 1);
+                            self.fuel.record_lap(&finished_lap);
+                            if let Err(e) = tyre_sets::save_inventory(app_handle, &self.tyres.inventory) {
+                                error!("Failed to save tyre sets: {}", e);
+                            }
+                            if let Some(weekend) = &self.weekend {
+                                if let Err(e) = weekend::save_weekend(app_handle, weekend) {
+                                    error!("Failed to save weekend: {}", e);
+                                }
+                            }
+                            if let Some(history) = self.power_unit.as_ref().filter(|history| !history.samples.is_empty()) {
+                                if let Err(e) = power_unit::save_history(app_handle, history) {
+                                    error!("Failed to save power unit wear: {}", e);
+                                }
+                            }
+
+                            // pit laps and invalid laps don't reflect the car's pace
+                            if tyre_lap.is_some() && !finished_lap.lap_invalid {
+                                if let Some(strategy) = self.strategy(&finished_lap) {
+                                    if let Err(e) = app_handle.emit("strategy", strategy) {
+                                        error!("{:#?}", e);
+                                    }
+                                }
+                            }
+
+                            if let Some(undercut) = self.undercut() {
+                                if let Err(e) = app_handle.emit("undercut", undercut) {
+                                    error!("{:#?}", e);
+                                }
+                            }
                             match self.post_new_lap(&finished_lap, store).await {
                                 Ok(_) => info!("Created new telemetry lap on backend"),
                                 Err(e) => error!("{:#?}", e),
                             }
+
+                            if let Some(setup_lap) = self.setup_lap(&finished_lap) {
+                                if let Err(e) = setups::save_setup_lap(app_handle, &setup_lap) {
+                                    error!("Failed to save setup lap: {}", e);
+                                }
+                            }
+
+                            if self.update_track_limits(&finished_lap) {
+                                if let Some(track_limits) = &self.track_limits {
+                                    if let Err(e) = tracks::save_track_limits(app_handle, track_limits) {
+                                        error!("Failed to save track limits: {}", e);
+                                    }
+                                }
+                            }
+
+                            if self.update_reference_lap(&finished_lap) {
+                                info!("New personal best, switching live delta reference");
+                                if let Some(reference) = &self.reference_lap {
+                                    if let Err(e) = records::save_reference_lap(app_handle, reference) {
+                                        error!("Failed to save reference lap: {}", e);
+                                    }
+                                }
+                            }
+
+                            if self.update_records(&finished_lap) {
+                                if let Some(key) = &self.records_key {
+                                    if let Err(e) = records::save_track_records(app_handle, key, &self.track_records) {
+                                        error!("Failed to save track records: {}", e);
+                                    }
+                                }
+                            }
+
+                            if self.collect_segment_lap(finished_lap) {
+                                info!("Detected corners for track {:?}", self.track_id);
+                                upload_segment_map(self, store, app_handle).await;
+                            }
             
-                            self.current_lap = Some(Lap::new(lap_data, self.assists.clone()));
+                            self.current_lap = Some(self.new_lap(lap_data));
                         } else {
                             self.current_lap = None;
                         }
                     }
                     None => {
-                        self.current_lap = Some(Lap::new(lap_data, self.assists.clone()));
+                        self.current_lap = Some(self.new_lap(lap_data));
+                    }
+                }
+
+                // broadcast, so it reaches both the main window and the overlay
+                if let Some(delta) = self.live_delta(&lap_data) {
+                    if let Err(e) = app_handle.emit("live-delta", delta) {
+                        error!("{:#?}", e);
+                    }
+                }
+            }
+            Packet::CarDamage(p) => {
+                let car_damage_data = p.car_damage_data[self.player_car_index as usize];
+                self.tyres.update_damage(&car_damage_data);
+                if let Some(lap_data) = self.field.lap_data.get(self.player_car_index as usize) {
+                    let (lap_number, lap_distance) = (lap_data.current_lap_num, lap_data.lap_distance);
+                    if let Some(collision) = self.collision_detector.update_damage(&car_damage_data, p.header.session_time, lap_number, lap_distance) {
+                        info!("Detected {:?} collision", collision.severity);
+                        let incident = self.incidents.record_collision(self.player_car_index, &collision);
+                        if let Some(lap) = &mut self.current_lap {
+                            lap.record_incident(&incident);
+                        }
+                        if let Err(e) = app_handle.emit("incident", incident) {
+                            error!("{:#?}", e);
+                        }
+                    }
+                }
+                if let Some(lap) = &mut self.current_lap {
+                    lap.damage = Some(DamageState::from_damage(&car_damage_data));
+                }
+                if let Some(weekend) = &mut self.weekend {
+                    weekend.engine_wear = Some(EngineWear::from_damage(&car_damage_data));
+                }
+                self.record_power_unit(&car_damage_data);
+            }
+            Packet::TyreSets(p) if p.car_idx == self.player_car_index => {
+                let fitted_changed = self.tyres.update_sets(&p);
+                if fitted_changed {
+                    if let Err(e) = tyre_sets::save_inventory(app_handle, &self.tyres.inventory) {
+                        error!("Failed to save tyre sets: {}", e);
                     }
                 }
             }
+            Packet::Event(p) => {
+                if let Some(incident) = self.incidents.record(&p, &self.field.lap_data) {
+                    self.record_invalidations(&incident);
+                    if incident.involves(self.player_car_index) {
+                        if let Some(lap) = &mut self.current_lap {
+                            lap.record_incident(&incident);
+                        }
+                    }
+                    if let Err(e) = app_handle.emit("incident", incident) {
+                        error!("{:#?}", e);
+                    }
+                }
+            }
+            Packet::CarSetups(p) => {
+                let setup_changed = self.record_setup(CarSetup::from(&p.car_setups[self.player_car_index as usize]));
+                if setup_changed {
+                    info!("Setup changed");
+                }
+            }
+            Packet::Participants(p) => {
+                self.participants = Participant::from_packet(&p);
+            }
+            Packet::FinalClassification(p) => {
+                info!("Received final classification");
+                self.classification = Some(Classification::new(&p, &self.participants));
+                if let Err(e) = results::save_result(app_handle, self) {
+                    error!("Failed to save session result: {}", e);
+                }
+            }
+            Packet::SessionHistory(p) if p.car_idx == self.player_car_index => {
+                self.tyres.apply_history(&p);
+            }
             Packet::CarTelemetry(p) => {
                 if let Some(lap) = &mut self.current_lap {
                     if lap.driver_status == 1 {
-                        let telemetry_data = JSONCarTelemetryData::new(p.car_telemetry_data[self.player_car_index as usize], lap.lap_time_in_ms);
+                        let telemetry_data = JSONCarTelemetryData::new(p.car_telemetry_data[self.player_car_index as usize], lap.lap_time.as_millis());
                         lap.car_telemetry.insert(telemetry_data.current_lap_time_in_ms, telemetry_data);
                     }
                 }
             }
-            // Packet::Motion(p) => {
-            //     self.motion_data.push(p.car_motion_data[self.player_car_index as usize]);
-            // }
-            // Packet::MotionEx(p) => {
-            //     self.motion_ex_data.push(MotionExData::from(p));
-            // }
+            Packet::Motion(p) => {
+                self.collision_detector.update_motion(&p.car_motion_data[self.player_car_index as usize], p.header.session_time);
+                if let Some(lap) = &mut self.current_lap {
+                    if lap.driver_status == 1 {
+                        let motion_data = JSONCarMotionData::new(p.car_motion_data[self.player_car_index as usize], lap.lap_time.as_millis());
+                        lap.car_motion.insert(motion_data.current_lap_time_in_ms, motion_data);
+                    }
+                }
+            }
+            Packet::MotionEx(p) => {
+                if let Some(lap) = &mut self.current_lap {
+                    if lap.driver_status == 1 {
+                        lap.motion_ex.insert(lap.lap_time.as_millis(), MotionExData::from(p));
+                    }
+                }
+            }
             _ => {}
         }
     }
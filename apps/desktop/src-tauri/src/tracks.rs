@@ -0,0 +1,59 @@
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+use telemetry::{analysis::{SegmentMap, TrackLimits}, pit_stops::PitLoss};
+
+/// Store holding per-track data learned from the player's laps, keyed by track ID.
+const SEGMENTS_STORE: &str = "track_segments.json";
+/// Store holding the measured time lost to a pit stop, keyed by track ID.
+const PIT_LOSS_STORE: &str = "pit_loss.json";
+/// Store holding how often the player leaves the track at each corner, keyed by track ID.
+const TRACK_LIMITS_STORE: &str = "track_limits.json";
+
+pub fn load_segment_map(app_handle: &AppHandle, track_id: i8) -> Option<SegmentMap> {
+    let store = app_handle.store(SEGMENTS_STORE).ok()?;
+    serde_json::from_value(store.get(track_id.to_string())?).ok()
+}
+
+pub fn save_segment_map(app_handle: &AppHandle, segment_map: &SegmentMap) -> Result<(), String> {
+    let store = app_handle.store(SEGMENTS_STORE).map_err(|err| err.to_string())?;
+    let value = serde_json::to_value(segment_map).map_err(|err| err.to_string())?;
+
+    store.set(segment_map.track_id.to_string(), value);
+    store.save().map_err(|err| err.to_string())
+}
+
+pub fn load_pit_loss(app_handle: &AppHandle, track_id: i8) -> Option<PitLoss> {
+    let store = app_handle.store(PIT_LOSS_STORE).ok()?;
+    serde_json::from_value(store.get(track_id.to_string())?).ok()
+}
+
+pub fn save_pit_loss(app_handle: &AppHandle, track_id: i8, pit_loss: &PitLoss) -> Result<(), String> {
+    let store = app_handle.store(PIT_LOSS_STORE).map_err(|err| err.to_string())?;
+    let value = serde_json::to_value(pit_loss).map_err(|err| err.to_string())?;
+
+    store.set(track_id.to_string(), value);
+    store.save().map_err(|err| err.to_string())
+}
+
+pub fn load_track_limits(app_handle: &AppHandle, track_id: i8) -> Option<TrackLimits> {
+    let store = app_handle.store(TRACK_LIMITS_STORE).ok()?;
+    serde_json::from_value(store.get(track_id.to_string())?).ok()
+}
+
+pub fn save_track_limits(app_handle: &AppHandle, track_limits: &TrackLimits) -> Result<(), String> {
+    let store = app_handle.store(TRACK_LIMITS_STORE).map_err(|err| err.to_string())?;
+    let value = serde_json::to_value(track_limits).map_err(|err| err.to_string())?;
+
+    store.set(track_limits.track_id.to_string(), value);
+    store.save().map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+pub fn get_segment_map(app_handle: AppHandle, track_id: i8) -> Option<SegmentMap> {
+    load_segment_map(&app_handle, track_id)
+}
+
+#[tauri::command]
+pub fn get_track_limits(app_handle: AppHandle, track_id: i8) -> Option<TrackLimits> {
+    load_track_limits(&app_handle, track_id)
+}
@@ -0,0 +1,36 @@
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+use telemetry::tyres::TyreInventory;
+
+/// Store holding the player's tyre sets, keyed by weekend link identifier.
+const TYRE_SETS_STORE: &str = "tyre_sets.json";
+/// Key of the weekend the player last drove in.
+const LATEST_KEY: &str = "latest";
+
+fn load(app_handle: &AppHandle, weekend_link_identifier: u32) -> Option<TyreInventory> {
+    let store = app_handle.store(TYRE_SETS_STORE).ok()?;
+    serde_json::from_value(store.get(weekend_link_identifier.to_string())?).ok()
+}
+
+/// Tyre sets carried over from earlier sessions of the weekend, or a fresh inventory
+/// if this is the first session of it.
+pub fn load_inventory(app_handle: &AppHandle, weekend_link_identifier: u32) -> TyreInventory {
+    load(app_handle, weekend_link_identifier).unwrap_or_else(|| TyreInventory::new(weekend_link_identifier))
+}
+
+pub fn save_inventory(app_handle: &AppHandle, inventory: &TyreInventory) -> Result<(), String> {
+    let store = app_handle.store(TYRE_SETS_STORE).map_err(|err| err.to_string())?;
+    let value = serde_json::to_value(inventory).map_err(|err| err.to_string())?;
+
+    store.set(inventory.weekend_link_identifier.to_string(), value);
+    store.set(LATEST_KEY, inventory.weekend_link_identifier);
+    store.save().map_err(|err| err.to_string())
+}
+
+/// Tyre sets from the weekend the player last drove in.
+#[tauri::command]
+pub fn get_tyre_inventory(app_handle: AppHandle) -> Option<TyreInventory> {
+    let store = app_handle.store(TYRE_SETS_STORE).ok()?;
+    let weekend_link_identifier: u32 = serde_json::from_value(store.get(LATEST_KEY)?).ok()?;
+    load(&app_handle, weekend_link_identifier)
+}
@@ -0,0 +1,73 @@
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+use telemetry::{session::Session, weekend::{Championship, Weekend, WeekendLink}};
+
+use crate::tyre_sets;
+
+/// Store holding race weekends and the championships they belong to.
+const WEEKENDS_STORE: &str = "weekends.json";
+
+fn weekend_key(weekend_link_identifier: u32) -> String {
+    format!("weekend:{}", weekend_link_identifier)
+}
+
+fn championship_key(season_link_identifier: u32) -> String {
+    format!("season:{}", season_link_identifier)
+}
+
+fn load<T: serde::de::DeserializeOwned>(app_handle: &AppHandle, key: &str) -> Option<T> {
+    let store = app_handle.store(WEEKENDS_STORE).ok()?;
+    serde_json::from_value(store.get(key)?).ok()
+}
+
+fn save<T: serde::Serialize>(app_handle: &AppHandle, key: String, value: &T) -> Result<(), String> {
+    let store = app_handle.store(WEEKENDS_STORE).map_err(|err| err.to_string())?;
+    let value = serde_json::to_value(value).map_err(|err| err.to_string())?;
+
+    store.set(key, value);
+    store.save().map_err(|err| err.to_string())
+}
+
+pub fn save_weekend(app_handle: &AppHandle, weekend: &Weekend) -> Result<(), String> {
+    save(app_handle, weekend_key(weekend.weekend_link_identifier), weekend)
+}
+
+/// Attaches the session to its weekend, loading the weekend (and the tyre sets carried
+/// through it) whenever the player moves on to a new one.
+/// Returns true if the player has joined a session of a weekend that has not been created
+/// on the backend yet, so a failed post is retried once per session rather than every packet.
+pub fn sync_weekend(session: &mut Session, app_handle: &AppHandle, link: WeekendLink, track_id: i8) -> Result<bool, String> {
+    if session.link == Some(link) {
+        return Ok(false);
+    }
+
+    let same_weekend = session.link.is_some_and(|previous| previous.weekend_link_identifier == link.weekend_link_identifier);
+    session.link = Some(link);
+    if !same_weekend || session.weekend.is_none() {
+        session.tyres.inventory = tyre_sets::load_inventory(app_handle, link.weekend_link_identifier);
+        session.weekend = Some(
+            load(app_handle, &weekend_key(link.weekend_link_identifier)).unwrap_or_else(|| Weekend::new(link, track_id)),
+        );
+
+        let key = championship_key(link.season_link_identifier);
+        let mut championship = load(app_handle, &key).unwrap_or_else(|| Championship::new(link.season_link_identifier));
+        if championship.add_weekend(link.weekend_link_identifier) {
+            save(app_handle, key, &championship)?;
+        }
+    }
+
+    let Some(weekend) = &mut session.weekend else { return Ok(false) };
+    weekend.add_session(link.session_link_identifier, session.info.and_then(|info| info.session_type));
+    save_weekend(app_handle, weekend)?;
+    Ok(weekend.weekend_uid.is_none())
+}
+
+#[tauri::command]
+pub fn get_weekend(app_handle: AppHandle, weekend_link_identifier: u32) -> Option<Weekend> {
+    load(&app_handle, &weekend_key(weekend_link_identifier))
+}
+
+#[tauri::command]
+pub fn get_championship(app_handle: AppHandle, season_link_identifier: u32) -> Option<Championship> {
+    load(&app_handle, &championship_key(season_link_identifier))
+}
@@ -0,0 +1 @@
+ALTER TABLE "tracks" ADD COLUMN "segment_map" jsonb;
\ No newline at end of file
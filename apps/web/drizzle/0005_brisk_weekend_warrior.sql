@@ -0,0 +1,13 @@
+CREATE TABLE "weekends" (
+	"uid" text PRIMARY KEY NOT NULL,
+	"user_id" text NOT NULL,
+	"season_link_identifier" bigint NOT NULL,
+	"weekend_link_identifier" bigint NOT NULL,
+	"track_id" integer NOT NULL,
+	CONSTRAINT "weekends_user_id_weekend_link_identifier_unique" UNIQUE("user_id","weekend_link_identifier")
+);
+--> statement-breakpoint
+ALTER TABLE "telemetry_sessions" ADD COLUMN "weekend_uid" text;--> statement-breakpoint
+ALTER TABLE "weekends" ADD CONSTRAINT "weekends_user_id_users_id_fk" FOREIGN KEY ("user_id") REFERENCES "public"."users"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+ALTER TABLE "weekends" ADD CONSTRAINT "weekends_track_id_tracks_id_fk" FOREIGN KEY ("track_id") REFERENCES "public"."tracks"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+ALTER TABLE "telemetry_sessions" ADD CONSTRAINT "telemetry_sessions_weekend_uid_weekends_uid_fk" FOREIGN KEY ("weekend_uid") REFERENCES "public"."weekends"("uid") ON DELETE set null ON UPDATE no action;
\ No newline at end of file
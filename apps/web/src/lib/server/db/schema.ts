@@ -1,5 +1,6 @@
 import type { Telemetry } from "$lib/types";
 import {
+	bigint,
 	boolean,
 	doublePrecision,
 	index,
@@ -9,6 +10,7 @@ import {
 	primaryKey,
 	text,
 	timestamp,
+	unique,
 } from "drizzle-orm/pg-core";
 
 export const users = pgTable("users", {
@@ -51,8 +53,25 @@ export const tracks = pgTable("tracks", {
 	location: text("location").notNull(),
 	trackName: text("track_name").notNull(),
 	trackLength: integer("track_length").notNull(),
+	segmentMap: jsonb("segment_map").$type<Telemetry.SegmentMap>(),
 });
 
+export const weekends = pgTable(
+	"weekends",
+	{
+		uid: text("uid").primaryKey(),
+		userId: text("user_id")
+			.notNull()
+			.references(() => users.id, { onDelete: "cascade" }),
+		seasonLinkIdentifier: bigint("season_link_identifier", { mode: "number" }).notNull(),
+		weekendLinkIdentifier: bigint("weekend_link_identifier", { mode: "number" }).notNull(),
+		trackId: integer("track_id")
+			.notNull()
+			.references(() => tracks.id, { onDelete: "cascade" }),
+	},
+	(table) => [unique().on(table.userId, table.weekendLinkIdentifier)],
+);
+
 export const telemetrySessions = pgTable("telemetry_sessions", {
 	uid: text("uid").primaryKey(),
 	userId: text("user_id")
@@ -68,6 +87,7 @@ export const telemetrySessions = pgTable("telemetry_sessions", {
 	trackId: integer("track_id")
 		.notNull()
 		.references(() => tracks.id, { onDelete: "cascade" }),
+	weekendUid: text("weekend_uid").references(() => weekends.uid, { onDelete: "set null" }),
 });
 
 export const laps = pgTable(
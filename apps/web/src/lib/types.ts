@@ -6,6 +6,7 @@ import type {
 	telemetrySessions,
 	tracks,
 	users,
+	weekends,
 } from "./server/db/schema";
 
 export interface SessionMetadata {
@@ -24,6 +25,7 @@ export namespace Database {
 	export type Track = InferSelectModel<typeof tracks>;
 	export type TelemetrySession = InferSelectModel<typeof telemetrySessions>;
 	export type Lap = InferSelectModel<typeof laps>;
+	export type Weekend = InferSelectModel<typeof weekends>;
 
 	export type InsertUser = InferInsertModel<typeof users>;
 	export type InsertSession = InferInsertModel<typeof sessions>;
@@ -31,6 +33,7 @@ export namespace Database {
 	export type InsertTrack = InferInsertModel<typeof tracks>;
 	export type InsertTelemetrySession = InferInsertModel<typeof telemetrySessions>;
 	export type InsertLap = InferInsertModel<typeof laps>;
+	export type InsertWeekend = InferInsertModel<typeof weekends>;
 	// export interface User {
 	// 	id: string;
 	// 	username: string;
@@ -124,6 +127,14 @@ export namespace Telemetry {
 		totalLaps: number;
 		trackId: number;
 		assists: number;
+		weekendUid: string | null;
+	}
+
+	export interface Weekend {
+		weekendUid: string | null;
+		seasonLinkIdentifier: number;
+		weekendLinkIdentifier: number;
+		trackId: number;
 	}
 
 	export interface CarTelemetryData {
@@ -144,6 +155,21 @@ export namespace Telemetry {
 		currentLapTimeInMs: number;
 	}
 
+	export interface Corner {
+		number: number;
+		entryDistance: number;
+		apexDistance: number;
+		exitDistance: number;
+		apexSpeed: number;
+		apexPosition: [number, number] | null;
+	}
+
+	export interface SegmentMap {
+		trackId: number;
+		lapDistance: number;
+		corners: Corner[];
+	}
+
 	export type PlotOption = keyof Pick<
 		CarTelemetryData,
 		"speed" | "throttle" | "steer" | "brake" | "clutch" | "gear" | "engineRpm" | "drs"
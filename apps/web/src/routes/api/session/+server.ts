@@ -21,6 +21,7 @@ export const POST: RequestHandler = async ({ request, locals }) => {
 		timeOfDay: session.timeOfDay,
 		totalLaps: session.totalLaps,
 		trackId: session.trackId,
+		weekendUid: session.weekendUid,
 	} as Database.InsertTelemetrySession)}`;
 
 	const [track]: [Database.Track] = (
@@ -38,6 +39,7 @@ export const POST: RequestHandler = async ({ request, locals }) => {
 		weather: session.weather,
 		timeOfDay: session.timeOfDay,
 		totalLaps: session.totalLaps,
+		weekendUid: session.weekendUid,
 		track,
 		laps: [],
 	};
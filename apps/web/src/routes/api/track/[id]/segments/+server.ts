@@ -0,0 +1,39 @@
+import { db } from "$lib/server/db";
+import type { Telemetry } from "$lib/types";
+import type { RequestHandler } from "./$types";
+
+const headers = {
+	"Access-Control-Allow-Origin": "*",
+	"Access-Control-Allow-Methods": "GET, PUT, OPTIONS",
+	"Access-Control-Allow-Headers": "Content-Type",
+};
+
+export const GET: RequestHandler = async ({ params }) => {
+	const [track] = await db`SELECT segment_map FROM tracks WHERE tracks.id = ${params.id}`;
+	if (!track) {
+		return new Response(null, { status: 404 });
+	}
+
+	return new Response(JSON.stringify({ status: "success", segment_map: track.segmentMap }), {
+		status: 200,
+		headers,
+	});
+};
+
+export const PUT: RequestHandler = async ({ request, params, locals }) => {
+	if (!locals.user) {
+		return new Response(null, { status: 401 });
+	}
+	const segmentMap: Telemetry.SegmentMap = await request.json();
+	if (segmentMap.trackId !== Number(params.id)) {
+		return new Response(null, { status: 400 });
+	}
+
+	// Every user detects the same corners, so the first map stored for a track is kept
+	const result = await db`UPDATE tracks SET segment_map = ${db.json(segmentMap as never)} WHERE tracks.id = ${params.id} AND tracks.segment_map IS NULL`;
+
+	return new Response(JSON.stringify({ status: "success", updated: result.count > 0 }), {
+		status: 200,
+		headers,
+	});
+};
@@ -0,0 +1,34 @@
+import type { RequestHandler } from "@sveltejs/kit";
+import type { Database, Telemetry } from "$lib/types";
+import { db } from "$lib/server/db";
+import { generateID } from "$lib/id";
+
+export const POST: RequestHandler = async ({ request, locals }) => {
+	if (!locals.user) {
+		return new Response(null, { status: 401 });
+	}
+	const weekend: Telemetry.Weekend = await request.json();
+
+	// the desktop app retries if it never saw the response, so a weekend is only created once
+	await db`INSERT INTO weekends ${db({
+		uid: generateID(),
+		userId: locals.user.id,
+		seasonLinkIdentifier: weekend.seasonLinkIdentifier,
+		weekendLinkIdentifier: weekend.weekendLinkIdentifier,
+		trackId: weekend.trackId,
+	} as Database.InsertWeekend)} ON CONFLICT (user_id, weekend_link_identifier) DO NOTHING`;
+
+	const [{ uid }] = await db`
+		SELECT uid FROM weekends
+		WHERE weekends.user_id = ${locals.user.id} AND weekends.weekend_link_identifier = ${weekend.weekendLinkIdentifier}
+	`;
+
+	return new Response(JSON.stringify({ status: "success", weekend_uid: uid }), {
+		status: 200,
+		headers: {
+			"Access-Control-Allow-Origin": "*",
+			"Access-Control-Allow-Methods": "POST, OPTIONS",
+			"Access-Control-Allow-Headers": "Content-Type",
+		},
+	});
+};
@@ -0,0 +1,114 @@
+mod braking;
+mod excursions;
+mod segments;
+mod setup;
+mod traction;
+
+pub use braking::*;
+pub use excursions::*;
+pub use segments::*;
+pub use setup::*;
+pub use traction::*;
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{session::Lap, JSONCarTelemetryData, MotionExData};
+
+/// Wheels in the order the game sends per-wheel arrays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Wheel {
+    RearLeft,
+    RearRight,
+    FrontLeft,
+    FrontRight,
+}
+
+impl Wheel {
+    pub const ALL: [Wheel; 4] = [Wheel::RearLeft, Wheel::RearRight, Wheel::FrontLeft, Wheel::FrontRight];
+
+    pub fn is_front(&self) -> bool {
+        matches!(self, Wheel::FrontLeft | Wheel::FrontRight)
+    }
+}
+
+/// A time-keyed sample placed on the lap by distance.
+#[derive(Debug, Clone, Copy)]
+pub struct Located<T> {
+    pub lap_distance: f32,
+    pub current_lap_time_in_ms: u32,
+    pub data: T,
+}
+
+/// Places every sample of a lap at the distance the car had covered when it was recorded.
+/// Samples outside of the lap's distance trace are dropped.
+fn by_distance<T: Copy>(lap: &Lap, samples: &BTreeMap<u32, T>) -> Vec<Located<T>> {
+    samples
+        .iter()
+        .filter_map(|(time, data)| {
+            Some(Located { lap_distance: lap.distance_at_time(*time)?, current_lap_time_in_ms: *time, data: *data })
+        })
+        .collect()
+}
+
+pub fn telemetry_by_distance(lap: &Lap) -> Vec<Located<JSONCarTelemetryData>> {
+    by_distance(lap, &lap.car_telemetry)
+}
+
+pub fn motion_ex_by_distance(lap: &Lap) -> Vec<Located<MotionExData>> {
+    by_distance(lap, &lap.motion_ex)
+}
+
+/// A run of consecutive samples in which a wheel's slip ratio passed a threshold,
+/// i.e. a lockup under braking or wheelspin under power.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlipEvent {
+    pub wheel: Wheel,
+    /// Distance the slip started at (m)
+    pub lap_distance: f32,
+    pub duration_in_ms: u32,
+    /// Slip ratio furthest from zero during the event
+    pub peak_slip_ratio: f32,
+}
+
+/// Finds every run of consecutive samples for which `slipping` holds, per wheel.
+fn slip_events<F>(samples: &[&Located<MotionExData>], wheels: &[Wheel], slipping: F) -> Vec<SlipEvent>
+where
+    F: Fn(&Located<MotionExData>, Wheel) -> bool,
+{
+    let mut events = Vec::new();
+    for wheel in wheels {
+        let mut run: Vec<&Located<MotionExData>> = Vec::new();
+        for sample in samples {
+            if slipping(sample, *wheel) {
+                run.push(sample);
+            } else if !run.is_empty() {
+                events.push(slip_event(*wheel, &run));
+                run.clear();
+            }
+        }
+        if !run.is_empty() {
+            events.push(slip_event(*wheel, &run));
+        }
+    }
+
+    events
+}
+
+fn slip_event(wheel: Wheel, run: &[&Located<MotionExData>]) -> SlipEvent {
+    let (first, last) = (run[0], run[run.len() - 1]);
+    let peak_slip_ratio = run
+        .iter()
+        .map(|s| s.data.wheel_slip_ratio[wheel as usize])
+        .fold(0.0f32, |peak, slip| if slip.abs() > peak.abs() { slip } else { peak });
+
+    SlipEvent {
+        wheel,
+        lap_distance: first.lap_distance,
+        duration_in_ms: last.current_lap_time_in_ms - first.current_lap_time_in_ms,
+        peak_slip_ratio,
+    }
+}
@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+
+use crate::session::Lap;
+
+use super::{motion_ex_by_distance, slip_events, telemetry_by_distance, SegmentMap, SlipEvent, Wheel};
+
+/// Brake input above which the driver is considered to be braking
+const BRAKE_ON: f32 = 0.05;
+/// Steering input above which the driver is considered to have turned in
+const TURN_IN_STEER: f32 = 0.05;
+/// Slip ratio at or below which a wheel is considered locked. The slip ratio already
+/// compares each wheel's speed to the car's, so unlike `wheel_speed` it needs no
+/// threshold per corner speed.
+const LOCKUP_SLIP_RATIO: f32 = -0.2;
+/// How far before a corner's entry (m) to look for the brake point
+const BRAKE_POINT_LOOKBEHIND: f32 = 50.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CornerBraking {
+    pub corner: u8,
+    /// Distance of first brake application (m), `None` if the corner was taken without braking
+    pub brake_point_distance: Option<f32>,
+    /// Highest brake input (0.0 to 1.0)
+    pub peak_brake: f32,
+    /// Time spent braking with steering applied
+    pub trail_braking_in_ms: u32,
+    /// Minimum speed through the corner (km/h)
+    pub min_speed: u16,
+    pub lockups: Vec<SlipEvent>,
+}
+
+/// Difference between a lap's braking and the reference lap's for one corner.
+/// Positive values mean the lap braked later, harder, trailed longer or carried more speed.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrakingComparison {
+    pub corner: u8,
+    pub brake_point_delta: Option<f32>,
+    pub peak_brake_delta: f32,
+    pub trail_braking_delta_in_ms: i32,
+    pub min_speed_delta: i32,
+    pub lockups_delta: i32,
+}
+
+/// Computes braking metrics for every corner of the map.
+pub fn braking_metrics(lap: &Lap, map: &SegmentMap) -> Vec<CornerBraking> {
+    let telemetry = telemetry_by_distance(lap);
+    let motion_ex = motion_ex_by_distance(lap);
+
+    map.corners
+        .iter()
+        .filter_map(|corner| {
+            let start = corner.entry_distance - BRAKE_POINT_LOOKBEHIND;
+            let samples: Vec<_> = telemetry
+                .iter()
+                .filter(|s| start <= s.lap_distance && s.lap_distance < corner.exit_distance)
+                .collect();
+
+            let brake_point_distance = samples
+                .iter()
+                .find(|s| s.lap_distance <= corner.apex_distance && s.data.brake >= BRAKE_ON)
+                .map(|s| s.lap_distance);
+
+            let trail_braking_in_ms = samples
+                .windows(2)
+                .filter(|pair| pair[0].data.brake >= BRAKE_ON && pair[0].data.steer.abs() >= TURN_IN_STEER)
+                .map(|pair| pair[1].current_lap_time_in_ms - pair[0].current_lap_time_in_ms)
+                .sum();
+
+            let in_corner: Vec<_> = motion_ex
+                .iter()
+                .filter(|s| start <= s.lap_distance && s.lap_distance < corner.exit_distance)
+                .collect();
+            let lockups = slip_events(&in_corner, &Wheel::ALL, |sample, wheel| {
+                sample.data.wheel_slip_ratio[wheel as usize] <= LOCKUP_SLIP_RATIO
+            });
+
+            Some(CornerBraking {
+                corner: corner.number,
+                brake_point_distance,
+                peak_brake: samples.iter().map(|s| s.data.brake).fold(0.0, f32::max),
+                trail_braking_in_ms,
+                min_speed: samples.iter().map(|s| s.data.speed).min()?,
+                lockups,
+            })
+        })
+        .collect()
+}
+
+/// Compares a lap's braking against the reference lap, corner by corner.
+pub fn compare_braking(lap: &[CornerBraking], reference: &[CornerBraking]) -> Vec<BrakingComparison> {
+    lap.iter()
+        .filter_map(|corner| {
+            let reference = reference.iter().find(|r| r.corner == corner.corner)?;
+
+            Some(BrakingComparison {
+                corner: corner.corner,
+                brake_point_delta: corner.brake_point_distance.zip(reference.brake_point_distance).map(|(lap, reference)| lap - reference),
+                peak_brake_delta: corner.peak_brake - reference.peak_brake,
+                trail_braking_delta_in_ms: corner.trail_braking_in_ms as i32 - reference.trail_braking_in_ms as i32,
+                min_speed_delta: corner.min_speed as i32 - reference.min_speed as i32,
+                lockups_delta: corner.lockups.len() as i32 - reference.lockups.len() as i32,
+            })
+        })
+        .collect()
+}
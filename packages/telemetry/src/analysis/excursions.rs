@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{delta::ReferenceLap, session::Lap};
+
+use super::{telemetry_by_distance, SegmentMap};
+
+/// Surface IDs the car is allowed to use: tarmac and rumble strips.
+const ON_TRACK_SURFACES: [u8; 2] = [0, 1];
+/// Wheels off the track for the car to count as having left it.
+pub const OFF_TRACK_WHEELS: usize = 2;
+/// Distance after rejoining (m) over which time is still being lost to the excursion.
+const RECOVERY_DISTANCE: f32 = 100.0;
+
+/// A period with the car off the track.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Excursion {
+    /// Corner the car left the track at, if the track's corners are known
+    pub corner: Option<u8>,
+    pub start_distance: f32,
+    pub end_distance: f32,
+    pub duration_in_ms: u32,
+    /// Most wheels off the track at once
+    pub max_wheels_off: u8,
+    /// Time lost against the reference lap up to shortly after rejoining (ms),
+    /// negative if time was gained
+    pub time_lost_in_ms: Option<i32>,
+}
+
+fn wheels_off(surface_type: [u8; 4]) -> usize {
+    surface_type.iter().filter(|s| !ON_TRACK_SURFACES.contains(s)).count()
+}
+
+/// Finds every run of samples with at least `OFF_TRACK_WHEELS` wheels off the track.
+pub fn excursions(lap: &Lap, map: Option<&SegmentMap>, reference: Option<&ReferenceLap>) -> Vec<Excursion> {
+    let samples = telemetry_by_distance(lap);
+
+    let mut runs = Vec::new();
+    let mut start: Option<usize> = None;
+    for (i, sample) in samples.iter().enumerate() {
+        let off = wheels_off(sample.data.surface_type) >= OFF_TRACK_WHEELS;
+        match (off, start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                runs.push(&samples[s..i]);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        runs.push(&samples[s..]);
+    }
+
+    runs.into_iter()
+        .map(|run| {
+            let (first, last) = (&run[0], &run[run.len() - 1]);
+            let start_distance = first.lap_distance;
+            let end_distance = last.lap_distance;
+            let time_lost_in_ms = reference.and_then(|reference| {
+                let recovered = end_distance + RECOVERY_DISTANCE;
+                let delta_before = lap.time_at_distance(start_distance)? as i32 - reference.time_at_distance(start_distance)? as i32;
+                let delta_after = lap.time_at_distance(recovered)? as i32 - reference.time_at_distance(recovered)? as i32;
+                Some(delta_after - delta_before)
+            });
+
+            Excursion {
+                corner: map.and_then(|map| map.corner_at(start_distance).or(map.corner_at(end_distance))).map(|c| c.number),
+                start_distance,
+                end_distance,
+                duration_in_ms: last.current_lap_time_in_ms - first.current_lap_time_in_ms,
+                max_wheels_off: run.iter().map(|s| wheels_off(s.data.surface_type)).max().unwrap_or(0) as u8,
+                time_lost_in_ms,
+            }
+        })
+        .collect()
+}
+
+/// How often the player leaves the track at a corner.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CornerTrackLimits {
+    pub excursions: u32,
+    /// Laps the corner was run on with at least one excursion
+    pub laps_with_excursion: u32,
+    pub total_time_lost_in_ms: i64,
+}
+
+/// Excursions at each corner of a track, kept across sessions.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackLimits {
+    pub track_id: i8,
+    pub laps: u32,
+    /// Keyed by corner number
+    pub corners: BTreeMap<u8, CornerTrackLimits>,
+    /// Excursions away from any known corner
+    pub elsewhere: CornerTrackLimits,
+}
+
+impl TrackLimits {
+    pub fn new(track_id: i8) -> Self {
+        Self { track_id, ..Default::default() }
+    }
+
+    pub fn record_lap(&mut self, excursions: &[Excursion]) {
+        self.laps += 1;
+
+        let mut corners_hit: Vec<Option<u8>> = Vec::new();
+        for excursion in excursions {
+            let limits = match excursion.corner {
+                Some(corner) => self.corners.entry(corner).or_default(),
+                None => &mut self.elsewhere,
+            };
+            limits.excursions += 1;
+            limits.total_time_lost_in_ms += excursion.time_lost_in_ms.unwrap_or(0) as i64;
+            if !corners_hit.contains(&excursion.corner) {
+                limits.laps_with_excursion += 1;
+                corners_hit.push(excursion.corner);
+            }
+        }
+    }
+
+    /// Share of laps with an excursion at the corner.
+    pub fn excursion_rate(&self, corner: u8) -> f32 {
+        if self.laps == 0 {
+            return 0.0;
+        }
+        self.corners.get(&corner).map_or(0.0, |limits| limits.laps_with_excursion as f32 / self.laps as f32)
+    }
+}
@@ -0,0 +1,326 @@
+use serde::{Deserialize, Serialize};
+
+use crate::session::Lap;
+
+use super::telemetry_by_distance;
+
+/// Resolution of the averaged speed profile (m)
+const BIN_SIZE: f32 = 5.0;
+/// Distance either side of an apex (m) in which it has to be the slowest point
+const APEX_WINDOW: f32 = 80.0;
+/// Minimum drop in speed (km/h) from the preceding straight for a slowdown to count as a corner
+const MIN_SPEED_DROP: f32 = 12.0;
+/// Throttle above which the driver is considered back on the power
+const FULL_THROTTLE: f32 = 0.95;
+/// A lap has to start this close to the line (m) to be used for detection
+const MAX_START_DISTANCE: f32 = 50.0;
+
+/// Number of clean laps needed before a segment map is built
+pub const REQUIRED_CLEAN_LAPS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Corner {
+    /// Turn number, counted from the start line
+    pub number: u8,
+    /// Distance at which the car starts slowing for the corner (m)
+    pub entry_distance: f32,
+    /// Distance of the slowest point of the corner (m)
+    pub apex_distance: f32,
+    /// Distance at which the driver is back on full throttle (m)
+    pub exit_distance: f32,
+    /// Averaged minimum speed (km/h)
+    pub apex_speed: f32,
+    /// World space X/Z position of the apex
+    pub apex_position: Option<[f32; 2]>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type", content = "corner")]
+pub enum SegmentKind {
+    Straight,
+    /// Braking and turn in, up to the apex of the given corner
+    Entry(u8),
+    /// Apex to full throttle of the given corner
+    Exit(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Segment {
+    pub kind: SegmentKind,
+    pub start_distance: f32,
+    pub end_distance: f32,
+}
+
+/// Per-lap measurements for one segment of the track.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LapSegment {
+    pub segment: Segment,
+    pub entry_speed: u16,
+    pub min_speed: u16,
+    pub exit_speed: u16,
+    pub time_in_ms: u32,
+}
+
+/// Corners detected on a track, used to split laps into named segments.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SegmentMap {
+    pub track_id: i8,
+    /// Furthest distance covered by the laps the map was built from (m)
+    pub lap_distance: f32,
+    pub corners: Vec<Corner>,
+}
+
+/// Averaged profiles of a set of laps, binned by distance.
+struct Profile {
+    speed: Vec<f32>,
+    throttle: Vec<f32>,
+}
+
+impl Profile {
+    fn build(laps: &[&Lap], lap_distance: f32) -> Option<Self> {
+        let bins = (lap_distance / BIN_SIZE).ceil() as usize + 1;
+        let mut sums = vec![(0.0f32, 0.0f32, 0u32); bins];
+
+        for lap in laps {
+            for sample in telemetry_by_distance(lap) {
+                let bin = &mut sums[(sample.lap_distance / BIN_SIZE) as usize];
+                bin.0 += sample.data.speed as f32;
+                bin.1 += sample.data.throttle;
+                bin.2 += 1;
+            }
+        }
+
+        // bins without samples take the value of the previous one
+        let first = sums.iter().find(|(_, _, count)| *count > 0)?;
+        let mut last = (first.0 / first.2 as f32, first.1 / first.2 as f32);
+        let (mut speed, mut throttle) = (Vec::with_capacity(bins), Vec::with_capacity(bins));
+        for (speed_sum, throttle_sum, count) in sums {
+            if count > 0 {
+                last = (speed_sum / count as f32, throttle_sum / count as f32);
+            }
+            speed.push(last.0);
+            throttle.push(last.1);
+        }
+
+        Some(Self { speed: smooth(&speed, 2), throttle })
+    }
+}
+
+fn smooth(values: &[f32], radius: usize) -> Vec<f32> {
+    (0..values.len())
+        .map(|i| {
+            let window = &values[i.saturating_sub(radius)..(i + radius + 1).min(values.len())];
+            window.iter().sum::<f32>() / window.len() as f32
+        })
+        .collect()
+}
+
+/// A lap can be used for detection if it is valid and was timed from the line.
+pub fn is_clean_lap(lap: &Lap) -> bool {
+    !lap.lap_invalid
+        && !lap.lap_time.is_zero()
+        && lap.distance_trace.first().is_some_and(|s| s.lap_distance < MAX_START_DISTANCE)
+}
+
+impl SegmentMap {
+    /// Detects corners from the speed and throttle traces of a few clean laps.
+    /// Returns `None` if none of the laps are clean.
+    pub fn detect(track_id: i8, laps: &[Lap]) -> Option<Self> {
+        let laps: Vec<&Lap> = laps.iter().filter(|lap| is_clean_lap(lap)).collect();
+        let lap_distance = laps
+            .iter()
+            .filter_map(|lap| lap.distance_trace.last().map(|s| s.lap_distance))
+            .fold(0.0f32, f32::max);
+        let profile = Profile::build(&laps, lap_distance)?;
+
+        let window = (APEX_WINDOW / BIN_SIZE) as usize;
+        let speed = &profile.speed;
+        let mut corners: Vec<Corner> = Vec::new();
+        let mut i = 0;
+        while i < speed.len() {
+            let range = &speed[i.saturating_sub(window)..(i + window + 1).min(speed.len())];
+            if range.iter().any(|s| *s < speed[i]) {
+                i += 1;
+                continue;
+            }
+
+            // walk back to where the car started slowing down
+            let mut entry = i;
+            while entry > 0 && speed[entry - 1] > speed[entry] {
+                entry -= 1;
+            }
+            if speed[entry] - speed[i] < MIN_SPEED_DROP {
+                i += 1;
+                continue;
+            }
+
+            // and forward to where the driver is back on the power
+            let mut exit = i;
+            while exit + 1 < speed.len() && profile.throttle[exit] < FULL_THROTTLE && speed[exit + 1] >= speed[exit] {
+                exit += 1;
+            }
+
+            let previous_exit = corners.last().map_or(0.0, |c| c.exit_distance);
+            let apex_distance = i as f32 * BIN_SIZE;
+            corners.push(Corner {
+                number: corners.len() as u8 + 1,
+                entry_distance: (entry as f32 * BIN_SIZE).max(previous_exit),
+                apex_distance,
+                exit_distance: exit as f32 * BIN_SIZE,
+                apex_speed: speed[i],
+                apex_position: laps.first().and_then(|lap| position_at(lap, apex_distance)),
+            });
+            i = exit.max(i + window) + 1;
+        }
+
+        Some(Self { track_id, lap_distance, corners })
+    }
+
+    /// The track split into straights, corner entries and corner exits, in lap order.
+    pub fn segments(&self) -> Vec<Segment> {
+        let mut segments = Vec::new();
+        let mut distance = 0.0;
+        for corner in &self.corners {
+            if corner.entry_distance > distance {
+                segments.push(Segment { kind: SegmentKind::Straight, start_distance: distance, end_distance: corner.entry_distance });
+            }
+            segments.push(Segment { kind: SegmentKind::Entry(corner.number), start_distance: corner.entry_distance, end_distance: corner.apex_distance });
+            segments.push(Segment { kind: SegmentKind::Exit(corner.number), start_distance: corner.apex_distance, end_distance: corner.exit_distance });
+            distance = corner.exit_distance;
+        }
+        if self.lap_distance > distance {
+            segments.push(Segment { kind: SegmentKind::Straight, start_distance: distance, end_distance: self.lap_distance });
+        }
+
+        segments
+    }
+
+    pub fn segment_at(&self, lap_distance: f32) -> Option<Segment> {
+        self.segments()
+            .into_iter()
+            .find(|s| s.start_distance <= lap_distance && lap_distance < s.end_distance)
+    }
+
+    /// The corner whose entry or exit contains the given distance.
+    pub fn corner_at(&self, lap_distance: f32) -> Option<&Corner> {
+        self.corners
+            .iter()
+            .find(|c| c.entry_distance <= lap_distance && lap_distance < c.exit_distance)
+    }
+
+    /// Splits a lap's telemetry into the segments of this map.
+    pub fn split_lap(&self, lap: &Lap) -> Vec<LapSegment> {
+        let samples = telemetry_by_distance(lap);
+
+        self.segments()
+            .into_iter()
+            .filter_map(|segment| {
+                let in_segment: Vec<u16> = samples
+                    .iter()
+                    .filter(|s| segment.start_distance <= s.lap_distance && s.lap_distance < segment.end_distance)
+                    .map(|s| s.data.speed)
+                    .collect();
+                let start_time = lap.time_at_distance(segment.start_distance)?;
+                let end_time = lap.time_at_distance(segment.end_distance)?;
+
+                Some(LapSegment {
+                    segment,
+                    entry_speed: *in_segment.first()?,
+                    min_speed: *in_segment.iter().min()?,
+                    exit_speed: *in_segment.last()?,
+                    time_in_ms: end_time.saturating_sub(start_time),
+                })
+            })
+            .collect()
+    }
+}
+
+fn position_at(lap: &Lap, lap_distance: f32) -> Option<[f32; 2]> {
+    let time = lap.time_at_distance(lap_distance)?;
+    let (_, motion) = lap.car_motion.range(time..).next()?;
+    Some([motion.world_position_x, motion.world_position_z])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{delta::DistanceSample, lap_time::LapTime, JSONCarTelemetryData};
+
+    const LAP_DISTANCE: u32 = 2000;
+
+    /// A lap at 300 km/h with a linear slowdown to each `(apex, apex_speed)`, 150m either side.
+    fn synthetic_lap(corners: &[(f32, f32)], start_distance: u32) -> Lap {
+        let mut lap = Lap { lap_time: LapTime::from_millis(LAP_DISTANCE * 20), ..Default::default() };
+        for distance in (start_distance..=LAP_DISTANCE).step_by(10) {
+            let time = distance * 20;
+            let lap_distance = distance as f32;
+            let (speed, braking) = corners
+                .iter()
+                .map(|(apex, apex_speed)| {
+                    let offset = (lap_distance - apex).abs().min(150.0) / 150.0;
+                    (apex_speed + (300.0 - apex_speed) * offset, lap_distance <= *apex && offset < 1.0)
+                })
+                .fold((300.0f32, false), |(speed, braking), (s, b)| (speed.min(s), braking || b));
+
+            lap.distance_trace.push(DistanceSample { lap_distance, current_lap_time_in_ms: time });
+            lap.car_telemetry.insert(time, JSONCarTelemetryData {
+                speed: speed as u16,
+                throttle: if braking { 0.0 } else if speed < 300.0 { 0.6 } else { 1.0 },
+                ..Default::default()
+            });
+        }
+
+        lap
+    }
+
+    #[test]
+    fn detects_a_corner_at_each_slowdown() {
+        let laps: Vec<Lap> = (0..REQUIRED_CLEAN_LAPS).map(|_| synthetic_lap(&[(500.0, 100.0), (1400.0, 150.0)], 0)).collect();
+        let map = SegmentMap::detect(7, &laps).unwrap();
+
+        assert_eq!(map.track_id, 7);
+        assert_eq!(map.lap_distance, LAP_DISTANCE as f32);
+        assert_eq!(map.corners.len(), 2);
+        for (corner, (number, apex, apex_speed)) in map.corners.iter().zip([(1, 500.0, 100.0), (2, 1400.0, 150.0)]) {
+            assert_eq!(corner.number, number);
+            assert!((corner.apex_distance - apex).abs() <= 10.0, "{:?}", corner);
+            assert!((corner.apex_speed - apex_speed).abs() < 15.0, "{:?}", corner);
+            assert!(corner.entry_distance < corner.apex_distance && corner.apex_distance < corner.exit_distance);
+        }
+    }
+
+    #[test]
+    fn ignores_small_lifts() {
+        let laps = vec![synthetic_lap(&[(500.0, 295.0)], 0)];
+        let map = SegmentMap::detect(7, &laps).unwrap();
+
+        assert!(map.corners.is_empty());
+    }
+
+    #[test]
+    fn needs_a_lap_timed_from_the_line() {
+        let mut invalid = synthetic_lap(&[(500.0, 100.0)], 0);
+        invalid.lap_invalid = true;
+        let laps = vec![invalid, synthetic_lap(&[(500.0, 100.0)], 300)];
+
+        assert!(!laps.iter().any(is_clean_lap));
+        assert_eq!(SegmentMap::detect(7, &laps), None);
+    }
+
+    #[test]
+    fn segments_cover_the_whole_lap() {
+        let laps = vec![synthetic_lap(&[(500.0, 100.0), (1400.0, 150.0)], 0)];
+        let map = SegmentMap::detect(7, &laps).unwrap();
+        let segments = map.segments();
+
+        assert_eq!(segments.first().unwrap().start_distance, 0.0);
+        assert_eq!(segments.last().unwrap().end_distance, map.lap_distance);
+        assert!(segments.windows(2).all(|pair| pair[0].end_distance == pair[1].start_distance));
+        assert_eq!(map.segment_at(500.0).map(|s| s.kind), Some(SegmentKind::Exit(1)));
+        assert_eq!(map.corner_at(1350.0).map(|c| c.number), Some(2));
+    }
+}
@@ -0,0 +1,213 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{lap_time::LapTime, session::Lap, setup::{CarSetup, SetupChange}, strategy::TIME_PER_WEAR_IN_MS, tyres::TyreLap};
+
+use super::LapBalance;
+
+/// Laps a setup needs before it is compared against others.
+pub const MIN_LAPS_PER_SETUP: usize = 2;
+/// Width (°C) of the track temperature bands laps are grouped into.
+const TRACK_TEMPERATURE_BAND: f32 = 5.0;
+
+/// What a lap tells us about the setup it was driven on.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupLap {
+    pub track_id: i8,
+    pub weather: u8,
+    pub track_temperature: f32,
+    pub actual_compound: u8,
+    pub setup: CarSetup,
+    pub lap_time_in_ms: LapTime,
+    /// Lap time with the weight of the fuel and the wear on the tyres taken out (ms)
+    pub corrected_lap_time_in_ms: u32,
+    pub sector_times_in_ms: [u32; 3],
+    pub slip_angle_balance: Option<f32>,
+    pub wheelspin_count: Option<u32>,
+}
+
+impl SetupLap {
+    /// Returns `None` for invalid laps, or laps missing their setup, conditions or timing.
+    pub fn from_lap(
+        lap: &Lap,
+        track_id: i8,
+        actual_compound: u8,
+        fuel_corrected_lap_time: Option<LapTime>,
+        tyres: Option<&TyreLap>,
+        balance: Option<&LapBalance>,
+    ) -> Option<Self> {
+        if lap.lap_invalid || lap.lap_time.is_zero() {
+            return None;
+        }
+        let conditions = lap.conditions.as_ref()?;
+        let sector3_time = lap.sector3_time()?;
+
+        let tyre_correction_in_ms = tyres.map_or(0.0, |tyres| tyres.max_wear() * TIME_PER_WEAR_IN_MS).round() as u32;
+        let corrected_lap_time_in_ms = fuel_corrected_lap_time
+            .unwrap_or(lap.lap_time)
+            .as_millis()
+            .saturating_sub(tyre_correction_in_ms);
+
+        Some(Self {
+            track_id,
+            weather: conditions.weather,
+            track_temperature: conditions.track_temperature,
+            actual_compound,
+            setup: lap.setup?,
+            lap_time_in_ms: lap.lap_time,
+            corrected_lap_time_in_ms,
+            sector_times_in_ms: [lap.sector1_time.as_millis(), lap.sector2_time.as_millis(), sector3_time.as_millis()],
+            slip_angle_balance: balance.map(|b| b.slip_angle_balance),
+            wheelspin_count: balance.map(|b| b.wheelspin_count),
+        })
+    }
+
+    fn track_temperature_band(&self) -> i32 {
+        (self.track_temperature / TRACK_TEMPERATURE_BAND).round() as i32
+    }
+
+    /// Laps are only compared against laps at the same track on the same tyre, in the same
+    /// weather and at a similar track temperature.
+    fn same_conditions(&self, other: &SetupLap) -> bool {
+        self.track_id == other.track_id
+            && self.weather == other.weather
+            && self.actual_compound == other.actual_compound
+            && self.track_temperature_band() == other.track_temperature_band()
+    }
+}
+
+fn mean(values: impl Iterator<Item = f32>) -> Option<f32> {
+    let (sum, count) = values.fold((0.0, 0), |(sum, count), v| (sum + v, count + 1));
+    (count > 0).then(|| sum / count as f32)
+}
+
+/// Pace and balance of one setup.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupResult {
+    pub setup: CarSetup,
+    pub laps: u32,
+    pub mean_corrected_lap_time_in_ms: u32,
+    pub best_corrected_lap_time_in_ms: u32,
+    pub mean_sector_times_in_ms: [u32; 3],
+    pub slip_angle_balance: Option<f32>,
+    pub wheelspin_per_lap: Option<f32>,
+}
+
+impl SetupResult {
+    fn from_laps(laps: &[&SetupLap]) -> Self {
+        let mean_ms = |f: &dyn Fn(&SetupLap) -> u32| mean(laps.iter().map(|l| f(l) as f32)).unwrap_or_default().round() as u32;
+
+        Self {
+            setup: laps[0].setup,
+            laps: laps.len() as u32,
+            mean_corrected_lap_time_in_ms: mean_ms(&|l| l.corrected_lap_time_in_ms),
+            best_corrected_lap_time_in_ms: laps.iter().map(|l| l.corrected_lap_time_in_ms).min().unwrap_or_default(),
+            mean_sector_times_in_ms: [
+                mean_ms(&|l| l.sector_times_in_ms[0]),
+                mean_ms(&|l| l.sector_times_in_ms[1]),
+                mean_ms(&|l| l.sector_times_in_ms[2]),
+            ],
+            slip_angle_balance: mean(laps.iter().filter_map(|l| l.slip_angle_balance)),
+            wheelspin_per_lap: mean(laps.iter().filter_map(|l| l.wheelspin_count.map(|c| c as f32))),
+        }
+    }
+}
+
+/// The effect of going from one setup to the next.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupChangeEffect {
+    pub changes: Vec<SetupChange>,
+    /// Change in mean corrected lap time (ms), negative if the change helped
+    pub lap_time_delta_in_ms: i32,
+    pub sector_deltas_in_ms: [i32; 3],
+    /// Change in slip angle balance (rad), positive towards oversteer
+    pub balance_delta: Option<f32>,
+    pub wheelspin_delta: Option<f32>,
+}
+
+impl SetupChangeEffect {
+    fn between(before: &SetupResult, after: &SetupResult) -> Self {
+        let delta = |a: u32, b: u32| b as i32 - a as i32;
+
+        Self {
+            changes: before.setup.diff(&after.setup),
+            lap_time_delta_in_ms: delta(before.mean_corrected_lap_time_in_ms, after.mean_corrected_lap_time_in_ms),
+            sector_deltas_in_ms: [0, 1, 2]
+                .map(|i| delta(before.mean_sector_times_in_ms[i], after.mean_sector_times_in_ms[i])),
+            balance_delta: after.slip_angle_balance.zip(before.slip_angle_balance).map(|(a, b)| a - b),
+            wheelspin_delta: after.wheelspin_per_lap.zip(before.wheelspin_per_lap).map(|(a, b)| a - b),
+        }
+    }
+
+    pub fn helped(&self) -> bool {
+        self.lap_time_delta_in_ms < 0
+    }
+}
+
+/// Setups tried at a track in one set of conditions, fastest first.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupReport {
+    pub track_id: i8,
+    pub weather: u8,
+    pub track_temperature: f32,
+    pub actual_compound: u8,
+    pub setups: Vec<SetupResult>,
+    /// Each change of setup in the order they were made, ranked from most helpful to most harmful
+    pub changes: Vec<SetupChangeEffect>,
+}
+
+/// Groups laps by conditions and setup, and ranks the setup changes made in each group.
+/// Laps should be in the order they were driven. Setups with fewer than
+/// `MIN_LAPS_PER_SETUP` laps are left out.
+pub fn setup_reports(laps: &[SetupLap]) -> Vec<SetupReport> {
+    let mut groups: Vec<Vec<&SetupLap>> = Vec::new();
+    for lap in laps {
+        match groups.iter_mut().find(|group| group[0].same_conditions(lap)) {
+            Some(group) => group.push(lap),
+            None => groups.push(vec![lap]),
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter_map(|group| {
+            // setups in the order they were first run
+            let mut by_setup: Vec<Vec<&SetupLap>> = Vec::new();
+            for lap in &group {
+                match by_setup.iter_mut().find(|laps| laps[0].setup.same_setup(&lap.setup)) {
+                    Some(laps) => laps.push(lap),
+                    None => by_setup.push(vec![lap]),
+                }
+            }
+
+            let tried: Vec<SetupResult> = by_setup
+                .iter()
+                .filter(|laps| laps.len() >= MIN_LAPS_PER_SETUP)
+                .map(|laps| SetupResult::from_laps(laps))
+                .collect();
+            if tried.is_empty() {
+                return None;
+            }
+
+            let mut changes: Vec<SetupChangeEffect> =
+                tried.windows(2).map(|pair| SetupChangeEffect::between(&pair[0], &pair[1])).collect();
+            changes.sort_by_key(|change| change.lap_time_delta_in_ms);
+
+            let mut setups = tried;
+            setups.sort_by_key(|setup| setup.mean_corrected_lap_time_in_ms);
+
+            let first = group[0];
+            Some(SetupReport {
+                track_id: first.track_id,
+                weather: first.weather,
+                track_temperature: mean(group.iter().map(|l| l.track_temperature)).unwrap_or(first.track_temperature),
+                actual_compound: first.actual_compound,
+                setups,
+                changes,
+            })
+        })
+        .collect()
+}
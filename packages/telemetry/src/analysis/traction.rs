@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{session::Lap, MotionExData};
+
+use super::{motion_ex_by_distance, slip_events, Located, Segment, SegmentKind, SegmentMap, SlipEvent, Wheel};
+
+/// Slip ratio at or above which a rear wheel is considered to be spinning
+const WHEELSPIN_SLIP_RATIO: f32 = 0.15;
+/// Throttle input above which wheelspin is attributed to the driver rather than the kerbs
+const THROTTLE_ON: f32 = 0.2;
+/// How far past a corner's exit (m) to keep looking for wheelspin
+const EXIT_LOOKAHEAD: f32 = 100.0;
+/// Front minus rear slip angle (rad) within which the car is considered neutral
+const NEUTRAL_SLIP_ANGLE: f32 = 0.005;
+
+const REAR_WHEELS: [Wheel; 2] = [Wheel::RearLeft, Wheel::RearRight];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Balance {
+    Understeer,
+    Neutral,
+    Oversteer,
+}
+
+impl Balance {
+    /// Classifies the difference between the mean front and rear slip angles.
+    /// The front sliding more than the rear is understeer, and the other way round oversteer.
+    pub fn from_slip_angles(slip_angle_balance: f32) -> Self {
+        if slip_angle_balance > NEUTRAL_SLIP_ANGLE {
+            Balance::Understeer
+        } else if slip_angle_balance < -NEUTRAL_SLIP_ANGLE {
+            Balance::Oversteer
+        } else {
+            Balance::Neutral
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SegmentBalance {
+    pub segment: Segment,
+    /// Mean absolute front slip angle minus mean absolute rear slip angle (rad)
+    pub slip_angle_balance: f32,
+    pub balance: Balance,
+    /// Share of the total lateral force carried by the front tyres (0.0 to 1.0)
+    pub front_lateral_force_share: f32,
+    /// Rear wheelspin under power, only looked for on corner exits
+    pub wheelspin: Vec<SlipEvent>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LapBalance {
+    pub segments: Vec<SegmentBalance>,
+    /// Mean slip angle balance over all corner segments (rad)
+    pub slip_angle_balance: f32,
+    pub balance: Balance,
+    pub wheelspin_count: u32,
+}
+
+/// Classifies the balance of the car through each corner segment and flags wheelspin on exits.
+/// Returns `None` if the lap has no extended motion data in any corner.
+pub fn traction_and_balance(lap: &Lap, map: &SegmentMap) -> Option<LapBalance> {
+    let motion_ex = motion_ex_by_distance(lap);
+
+    let segments: Vec<SegmentBalance> = map
+        .segments()
+        .into_iter()
+        .filter(|segment| segment.kind != SegmentKind::Straight)
+        .filter_map(|segment| segment_balance(lap, &motion_ex, segment))
+        .collect();
+    if segments.is_empty() {
+        return None;
+    }
+
+    let slip_angle_balance = segments.iter().map(|s| s.slip_angle_balance).sum::<f32>() / segments.len() as f32;
+    Some(LapBalance {
+        slip_angle_balance,
+        balance: Balance::from_slip_angles(slip_angle_balance),
+        wheelspin_count: segments.iter().map(|s| s.wheelspin.len() as u32).sum(),
+        segments,
+    })
+}
+
+fn segment_balance(lap: &Lap, motion_ex: &[Located<MotionExData>], segment: Segment) -> Option<SegmentBalance> {
+    let in_segment: Vec<&Located<MotionExData>> = motion_ex
+        .iter()
+        .filter(|s| segment.start_distance <= s.lap_distance && s.lap_distance < segment.end_distance)
+        .collect();
+    if in_segment.is_empty() {
+        return None;
+    }
+
+    let (mut front_slip, mut rear_slip, mut front_force, mut total_force) = (0.0, 0.0, 0.0, 0.0);
+    for sample in &in_segment {
+        for wheel in Wheel::ALL {
+            let slip_angle = sample.data.wheel_slip_angle[wheel as usize].abs();
+            let lateral_force = sample.data.wheel_lat_force[wheel as usize].abs();
+            if wheel.is_front() {
+                front_slip += slip_angle;
+                front_force += lateral_force;
+            } else {
+                rear_slip += slip_angle;
+            }
+            total_force += lateral_force;
+        }
+    }
+    let slip_angle_balance = (front_slip - rear_slip) / (2 * in_segment.len()) as f32;
+
+    let wheelspin = match segment.kind {
+        SegmentKind::Exit(_) => {
+            let exit: Vec<&Located<MotionExData>> = motion_ex
+                .iter()
+                .filter(|s| segment.start_distance <= s.lap_distance && s.lap_distance < segment.end_distance + EXIT_LOOKAHEAD)
+                .collect();
+            slip_events(&exit, &REAR_WHEELS, |sample, wheel| {
+                sample.data.wheel_slip_ratio[wheel as usize] >= WHEELSPIN_SLIP_RATIO
+                    && throttle_at(lap, sample.current_lap_time_in_ms) >= THROTTLE_ON
+            })
+        }
+        _ => Vec::new(),
+    };
+
+    Some(SegmentBalance {
+        segment,
+        slip_angle_balance,
+        balance: Balance::from_slip_angles(slip_angle_balance),
+        front_lateral_force_share: if total_force > 0.0 { front_force / total_force } else { 0.5 },
+        wheelspin,
+    })
+}
+
+/// Throttle input at the latest telemetry sample before the given lap time.
+fn throttle_at(lap: &Lap, current_lap_time_in_ms: u32) -> f32 {
+    lap.car_telemetry
+        .range(..=current_lap_time_in_ms)
+        .next_back()
+        .map_or(0.0, |(_, telemetry)| telemetry.throttle)
+}
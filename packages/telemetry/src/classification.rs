@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{lap_time::LapTime, FinalClassificationData, PacketFinalClassificationData, PacketParticipantsData, ParticipantData};
+
+/// Who is driving a car, taken from the participants packet.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Participant {
+    pub car_index: u8,
+    pub name: String,
+    pub ai_controlled: bool,
+    pub driver_id: u8,
+    pub team_id: u8,
+    pub race_number: u8,
+    pub nationality: u8,
+}
+
+impl Participant {
+    pub fn new(car_index: u8, participant: &ParticipantData) -> Self {
+        // the name is null terminated, and may be cut short part way through a character
+        let name = participant.name;
+        let len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+
+        Self {
+            car_index,
+            name: String::from_utf8_lossy(&name[..len]).into_owned(),
+            ai_controlled: participant.ai_controlled == 1,
+            driver_id: participant.driver_id,
+            team_id: participant.team_id,
+            race_number: participant.race_number,
+            nationality: participant.nationality,
+        }
+    }
+
+    /// Every active car in the session.
+    pub fn from_packet(participants: &PacketParticipantsData) -> Vec<Self> {
+        participants
+            .participants
+            .iter()
+            .take(participants.num_active_cars_u8 as usize)
+            .enumerate()
+            .map(|(car_index, participant)| Self::new(car_index as u8, participant))
+            .collect()
+    }
+}
+
+/// A stint as reported on the results screen.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassifiedStint {
+    pub actual_compound: u8,
+    pub visual_compound: u8,
+    pub end_lap: u8,
+}
+
+/// One car's result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassifiedCar {
+    pub car_index: u8,
+    /// Driver name, if the participants packet was seen before the results
+    pub name: Option<String>,
+    pub team_id: Option<u8>,
+    pub position: u8,
+    pub grid_position: u8,
+    pub num_laps: u8,
+    pub points: u8,
+    pub num_pit_stops: u8,
+    /// 3 = finished, 4 = did not finish, 5 = disqualified, 6 = not classified, 7 = retired
+    pub result_status: u8,
+    pub best_lap_time_in_ms: LapTime,
+    /// Race time in seconds, before penalties
+    pub total_race_time: f64,
+    /// Penalty time in seconds
+    pub penalties_time: u8,
+    pub num_penalties: u8,
+    pub tyre_stints: Vec<ClassifiedStint>,
+}
+
+impl ClassifiedCar {
+    pub fn new(car_index: u8, data: &FinalClassificationData, participant: Option<&Participant>) -> Self {
+        let tyre_stints = (0..(data.num_tyre_stints as usize).min(8))
+            .map(|i| ClassifiedStint {
+                actual_compound: data.tyre_stints_actual[i],
+                visual_compound: data.tyre_stints_visual[i],
+                end_lap: data.tyre_stints_end_laps[i],
+            })
+            .collect();
+
+        Self {
+            car_index,
+            name: participant.map(|p| p.name.clone()),
+            team_id: participant.map(|p| p.team_id),
+            position: data.position,
+            grid_position: data.grid_position,
+            num_laps: data.num_laps,
+            points: data.points,
+            num_pit_stops: data.num_pit_stops,
+            result_status: data.result_status,
+            best_lap_time_in_ms: LapTime::from_millis(data.best_lap_time_in_ms),
+            total_race_time: data.total_race_time,
+            penalties_time: data.penalties_time,
+            num_penalties: data.num_penalties,
+            tyre_stints,
+        }
+    }
+
+    /// Race time in seconds with penalties added.
+    pub fn total_time_with_penalties(&self) -> f64 {
+        self.total_race_time + self.penalties_time as f64
+    }
+}
+
+/// The final classification of a session, ordered by finishing position.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Classification {
+    pub cars: Vec<ClassifiedCar>,
+}
+
+impl Classification {
+    pub fn new(classification: &PacketFinalClassificationData, participants: &[Participant]) -> Self {
+        let mut cars: Vec<ClassifiedCar> = classification
+            .classification_data
+            .iter()
+            .take(classification.num_cars as usize)
+            .enumerate()
+            .filter(|(_, data)| data.position > 0)
+            .map(|(car_index, data)| {
+                let participant = participants.iter().find(|p| p.car_index as usize == car_index);
+                ClassifiedCar::new(car_index as u8, data, participant)
+            })
+            .collect();
+        cars.sort_by_key(|car| car.position);
+
+        Self { cars }
+    }
+
+    pub fn car(&self, car_index: u8) -> Option<&ClassifiedCar> {
+        self.cars.iter().find(|car| car.car_index == car_index)
+    }
+}
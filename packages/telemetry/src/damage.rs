@@ -0,0 +1,249 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{CarDamageData, CarMotionData};
+
+/// Rise in total bodywork damage (%) between two damage packets taken as a hit.
+const MIN_DAMAGE_INCREASE: u32 = 2;
+/// Rise in a tyre's damage (%) between two damage packets taken as contact, such as a
+/// puncture. Wear only adds a fraction of a percent in that time.
+const MIN_TYRE_DAMAGE_JUMP: u8 = 10;
+/// Change in horizontal g-force between two motion samples that counts as an impact.
+pub const IMPACT_G_FORCE: f32 = 2.5;
+/// Impact big enough to count as a collision even if the car came away undamaged.
+const UNDAMAGED_IMPACT_G_FORCE: f32 = 2.0 * IMPACT_G_FORCE;
+/// How far back (s) an impact is looked for once damage shows up. The damage packet
+/// only arrives ten times a second, so it trails the motion packet that saw the hit.
+const IMPACT_WINDOW: f32 = 1.0;
+
+/// Bodywork and tyre damage of a car (%).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DamageState {
+    pub front_left_wing: u8,
+    pub front_right_wing: u8,
+    pub rear_wing: u8,
+    pub floor: u8,
+    pub diffuser: u8,
+    pub sidepod: u8,
+    /// Wheel order is RL, RR, FL, FR
+    pub tyres: [u8; 4],
+}
+
+impl DamageState {
+    pub fn from_damage(damage: &CarDamageData) -> Self {
+        Self {
+            front_left_wing: damage.front_left_wing_damage,
+            front_right_wing: damage.front_right_wing_damage,
+            rear_wing: damage.rear_wing_damage,
+            floor: damage.floor_damage,
+            diffuser: damage.diffuser_damage,
+            sidepod: damage.sidepod_damage,
+            tyres: damage.tyres_damage,
+        }
+    }
+
+    fn bodywork(&self) -> [u8; 6] {
+        [self.front_left_wing, self.front_right_wing, self.rear_wing, self.floor, self.diffuser, self.sidepod]
+    }
+
+    /// Total damage gained on the bodywork since an earlier state.
+    pub fn bodywork_increase_since(&self, earlier: &DamageState) -> u32 {
+        self.bodywork()
+            .iter()
+            .zip(earlier.bodywork())
+            .map(|(&now, before)| now.saturating_sub(before) as u32)
+            .sum()
+    }
+
+    /// Damage gained since an earlier state, one damage packet before. Tyre damage also rises
+    /// steadily with wear, so only tyres that jumped by `MIN_TYRE_DAMAGE_JUMP` are counted.
+    pub fn increase_since(&self, earlier: &DamageState) -> u32 {
+        let tyres: u32 = self
+            .tyres
+            .iter()
+            .zip(earlier.tyres)
+            .map(|(&now, before)| now.saturating_sub(before))
+            .filter(|&jump| jump >= MIN_TYRE_DAMAGE_JUMP)
+            .map(u32::from)
+            .sum();
+        self.bodywork_increase_since(earlier) + tyres
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CollisionSeverity {
+    Minor,
+    Moderate,
+    Major,
+}
+
+impl CollisionSeverity {
+    fn from_impact(damage_increase: u32, peak_g_force: f32) -> Self {
+        match (damage_increase, peak_g_force) {
+            (d, g) if d >= 40 || g >= 3.0 * IMPACT_G_FORCE => CollisionSeverity::Major,
+            (d, g) if d >= 15 || g >= 2.0 * IMPACT_G_FORCE => CollisionSeverity::Moderate,
+            _ => CollisionSeverity::Minor,
+        }
+    }
+}
+
+/// A hit that damaged the player's car.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Collision {
+    pub session_time: f32,
+    /// Lap the hit happened on (1-based lap number)
+    pub lap_number: u8,
+    pub lap_distance: f32,
+    /// World position (x, z) of the car at the impact, if one was seen
+    pub position: Option<[f32; 2]>,
+    /// Largest change in horizontal g-force around the hit, 0 if none was seen
+    pub peak_g_force: f32,
+    pub damage_increase: u32,
+    pub damage_before: DamageState,
+    pub damage_after: DamageState,
+    pub severity: CollisionSeverity,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Impact {
+    session_time: f32,
+    g_force: f32,
+    position: [f32; 2],
+}
+
+/// Watches the player's damage and g-forces for collisions.
+#[derive(Debug, Default, Clone)]
+pub struct CollisionDetector {
+    pub collisions: Vec<Collision>,
+    pub damage: Option<DamageState>,
+    last_g_force: Option<(f32, f32)>,
+    impacts: VecDeque<Impact>,
+}
+
+impl CollisionDetector {
+    /// Remembers any sudden change in g-force as a possible impact.
+    pub fn update_motion(&mut self, motion: &CarMotionData, session_time: f32) {
+        let g_force = (motion.g_force_lateral, motion.g_force_longitudinal);
+        if let Some((lateral, longitudinal)) = self.last_g_force {
+            let change = (g_force.0 - lateral).hypot(g_force.1 - longitudinal);
+            if change >= IMPACT_G_FORCE {
+                self.impacts.push_back(Impact {
+                    session_time,
+                    g_force: change,
+                    position: [motion.world_position_x, motion.world_position_z],
+                });
+            }
+        }
+        self.last_g_force = Some(g_force);
+
+        while self.impacts.front().is_some_and(|impact| session_time - impact.session_time > IMPACT_WINDOW) {
+            self.impacts.pop_front();
+        }
+    }
+
+    /// Compares the damage against the last packet, returning a collision if the bodywork
+    /// or a tyre took a hit, or the car took a heavy impact without being damaged. The
+    /// largest impact in the last second is taken as its cause.
+    pub fn update_damage(&mut self, damage: &CarDamageData, session_time: f32, lap_number: u8, lap_distance: f32) -> Option<Collision> {
+        let damage_after = DamageState::from_damage(damage);
+        let damage_before = self.damage.replace(damage_after)?;
+
+        let damage_increase = damage_after.increase_since(&damage_before);
+        let impact = self
+            .impacts
+            .iter()
+            .filter(|impact| session_time - impact.session_time <= IMPACT_WINDOW)
+            .max_by(|a, b| a.g_force.total_cmp(&b.g_force))
+            .copied();
+        let heavy_impact = impact.is_some_and(|impact| impact.g_force >= UNDAMAGED_IMPACT_G_FORCE);
+        if damage_increase < MIN_DAMAGE_INCREASE && !heavy_impact {
+            return None;
+        }
+
+        self.impacts.clear();
+        let peak_g_force = impact.map_or(0.0, |impact| impact.g_force);
+
+        let collision = Collision {
+            session_time,
+            lap_number,
+            lap_distance,
+            position: impact.map(|impact| impact.position),
+            peak_g_force,
+            damage_increase,
+            damage_before,
+            damage_after,
+            severity: CollisionSeverity::from_impact(damage_increase, peak_g_force),
+        };
+        self.collisions.push(collision);
+        Some(collision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn damage(front_left_wing: u8, tyres: [u8; 4]) -> CarDamageData {
+        // SAFETY: the packet is plain numbers, for which all zeroes is valid
+        let mut damage: CarDamageData = unsafe { std::mem::zeroed() };
+        damage.front_left_wing_damage = front_left_wing;
+        damage.tyres_damage = tyres;
+        damage
+    }
+
+    fn motion(g_force_lateral: f32) -> CarMotionData {
+        // SAFETY: as above
+        let mut motion: CarMotionData = unsafe { std::mem::zeroed() };
+        motion.g_force_lateral = g_force_lateral;
+        motion
+    }
+
+    #[test]
+    fn detects_bodywork_damage() {
+        let mut detector = CollisionDetector::default();
+        assert_eq!(detector.update_damage(&damage(0, [0; 4]), 10.0, 3, 500.0), None);
+        detector.update_motion(&motion(1.0), 10.05);
+        detector.update_motion(&motion(-3.0), 10.1);
+
+        let collision = detector.update_damage(&damage(20, [0; 4]), 10.2, 3, 510.0).unwrap();
+        assert_eq!(collision.damage_increase, 20);
+        assert_eq!(collision.peak_g_force, 4.0);
+        assert_eq!(collision.severity, CollisionSeverity::Moderate);
+        assert_eq!(detector.collisions.len(), 1);
+    }
+
+    #[test]
+    fn detects_a_puncture_but_not_tyre_wear() {
+        let mut detector = CollisionDetector::default();
+        detector.update_damage(&damage(0, [5; 4]), 10.0, 3, 500.0);
+        for (i, tyre_damage) in (6..=9).enumerate() {
+            let session_time = 10.1 + i as f32 * 0.1;
+            assert_eq!(detector.update_damage(&damage(0, [tyre_damage; 4]), session_time, 3, 500.0), None);
+        }
+
+        let collision = detector.update_damage(&damage(0, [9, 9, 60, 9]), 10.6, 3, 520.0).unwrap();
+        assert_eq!(collision.damage_increase, 51);
+        assert_eq!(collision.damage_after.tyres[2], 60);
+    }
+
+    #[test]
+    fn counts_a_heavy_impact_without_damage() {
+        let mut detector = CollisionDetector::default();
+        detector.update_damage(&damage(0, [0; 4]), 10.0, 3, 500.0);
+        detector.update_motion(&motion(0.0), 10.0);
+        detector.update_motion(&motion(3.0), 10.05);
+        // a kerb strike, not enough for a collision on its own
+        assert_eq!(detector.update_damage(&damage(0, [0; 4]), 10.1, 3, 505.0), None);
+
+        detector.update_motion(&motion(-4.0), 10.15);
+        let collision = detector.update_damage(&damage(0, [0; 4]), 10.2, 3, 510.0).unwrap();
+        assert_eq!(collision.damage_increase, 0);
+        assert_eq!(collision.peak_g_force, 7.0);
+        // the impact isn't reported twice
+        assert_eq!(detector.update_damage(&damage(0, [0; 4]), 10.3, 3, 515.0), None);
+    }
+}
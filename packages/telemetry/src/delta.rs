@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{analysis::{braking_metrics, CornerBraking, SegmentMap}, lap_time::LapTime, session::Lap, JSONCarTelemetryData, MotionExData};
+
+/// How far (m) from the start and end of the lap a reference's trace may begin and end.
+const TRACE_END_TOLERANCE: f32 = 50.0;
+
+/// A single point on the distance/time trace of a lap.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DistanceSample {
+    /// Distance around the lap in metres
+    pub lap_distance: f32,
+    /// Time into the lap at this distance (ms)
+    pub current_lap_time_in_ms: u32,
+}
+
+/// The lap a live delta is measured against, normally the player's best valid lap
+/// for a given track and assist combination.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceLap {
+    pub track_id: i8,
+    pub assists: u16,
+    pub lap_time_in_ms: LapTime,
+    pub distance_trace: Vec<DistanceSample>,
+    /// Per-corner braking on this lap, once the track's corners are known
+    #[serde(default)]
+    pub braking: Vec<CornerBraking>,
+    /// Telemetry of the lap, kept only until `braking` has been measured
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub car_telemetry: BTreeMap<u32, JSONCarTelemetryData>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub motion_ex: BTreeMap<u32, MotionExData>,
+}
+
+impl ReferenceLap {
+    /// Builds a reference from a finished lap. Returns `None` if the lap is invalid, has
+    /// no lap time or assists recorded, or its trace doesn't cover the whole lap, as on an
+    /// out lap or a lap joined part way through.
+    pub fn from_lap(lap: &Lap, track_id: i8, track_length: u16) -> Option<Self> {
+        let (first, last) = (lap.distance_trace.first()?, lap.distance_trace.last()?);
+        if lap.lap_invalid
+            || lap.lap_time.is_zero()
+            || first.lap_distance > TRACE_END_TOLERANCE
+            || last.lap_distance < track_length as f32 - TRACE_END_TOLERANCE
+        {
+            return None;
+        }
+
+        let assists = lap.assists.as_ref()?.get_mask().ok()?;
+
+        Some(Self {
+            track_id,
+            assists,
+            lap_time_in_ms: lap.lap_time,
+            distance_trace: lap.distance_trace.clone(),
+            braking: Vec::new(),
+            car_telemetry: lap.car_telemetry.clone(),
+            motion_ex: lap.motion_ex.clone(),
+        })
+    }
+
+    /// Measures braking into each corner of the map from the kept telemetry, which is then dropped.
+    /// Returns false if the braking was already measured.
+    pub fn measure_braking(&mut self, segment_map: &SegmentMap) -> bool {
+        if self.car_telemetry.is_empty() {
+            return false;
+        }
+
+        let lap = Lap {
+            distance_trace: self.distance_trace.clone(),
+            car_telemetry: std::mem::take(&mut self.car_telemetry),
+            motion_ex: std::mem::take(&mut self.motion_ex),
+            ..Default::default()
+        };
+        self.braking = braking_metrics(&lap, segment_map);
+        true
+    }
+
+    /// Key used to store and look up references for a track/assist combination.
+    pub fn key(track_id: i8, assists: u16) -> String {
+        format!("{}:{}", track_id, assists)
+    }
+
+    /// Linearly interpolates the reference time at the given lap distance.
+    /// Returns `None` outside of the recorded trace.
+    pub fn time_at_distance(&self, lap_distance: f32) -> Option<u32> {
+        time_at_distance(&self.distance_trace, lap_distance)
+    }
+
+    /// Delta against the reference in milliseconds. Negative values are ahead of the reference.
+    pub fn delta(&self, lap_distance: f32, current_lap_time_in_ms: u32) -> Option<i32> {
+        let reference_time = self.time_at_distance(lap_distance)?;
+        Some(current_lap_time_in_ms as i32 - reference_time as i32)
+    }
+}
+
+/// Linearly interpolates the lap time at a distance along a trace sorted by distance.
+/// Returns `None` outside of the recorded trace.
+pub fn time_at_distance(trace: &[DistanceSample], lap_distance: f32) -> Option<u32> {
+    let idx = trace.partition_point(|s| s.lap_distance < lap_distance);
+    let after = trace.get(idx)?;
+    if after.lap_distance == lap_distance {
+        return Some(after.current_lap_time_in_ms);
+    }
+
+    let before = trace.get(idx.checked_sub(1)?)?;
+    let fraction = (lap_distance - before.lap_distance) / (after.lap_distance - before.lap_distance);
+    let time_diff = after.current_lap_time_in_ms as f32 - before.current_lap_time_in_ms as f32;
+
+    Some(before.current_lap_time_in_ms + (fraction * time_diff).round() as u32)
+}
+
+/// Linearly interpolates the distance at a lap time along a trace sorted by distance.
+/// Returns `None` outside of the recorded trace.
+pub fn distance_at_time(trace: &[DistanceSample], current_lap_time_in_ms: u32) -> Option<f32> {
+    let idx = trace.partition_point(|s| s.current_lap_time_in_ms < current_lap_time_in_ms);
+    let after = trace.get(idx)?;
+    if after.current_lap_time_in_ms == current_lap_time_in_ms {
+        return Some(after.lap_distance);
+    }
+
+    let before = trace.get(idx.checked_sub(1)?)?;
+    let fraction = (current_lap_time_in_ms - before.current_lap_time_in_ms) as f32
+        / (after.current_lap_time_in_ms - before.current_lap_time_in_ms) as f32;
+
+    Some(before.lap_distance + fraction * (after.lap_distance - before.lap_distance))
+}
+
+/// Payload emitted to the frontend while a lap is in progress.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveDelta {
+    pub lap_number: u8,
+    pub lap_distance: f32,
+    pub current_lap_time_in_ms: u32,
+    pub reference_lap_time_in_ms: LapTime,
+    pub delta_in_ms: i32,
+}
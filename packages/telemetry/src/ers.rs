@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+
+use crate::CarStatusData;
+
+/// Lap time (ms) before which a reset of the per-lap counters is taken to be the line itself
+const LINE_RESET_WINDOW_IN_MS: u32 = 5000;
+
+/// A stretch of the lap over which the battery was being deployed.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErsDeployment {
+    pub start_distance: f32,
+    pub end_distance: f32,
+    /// Energy deployed over the stretch (J)
+    pub energy: f32,
+}
+
+/// Energy store usage over a single lap.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErsUsage {
+    /// Energy in the store when the lap started (J)
+    pub store_energy_at_start: f32,
+    /// Energy in the store at the latest update (J)
+    pub store_energy: f32,
+    /// Energy harvested this lap by the MGU-K (J)
+    pub harvested_mgu_k: f32,
+    /// Energy harvested this lap by the MGU-H (J)
+    pub harvested_mgu_h: f32,
+    /// Energy deployed this lap (J)
+    pub deployed: f32,
+    /// Time spent in each deploy mode (none, medium, hotlap, overtake)
+    pub time_in_mode_in_ms: [u32; 4],
+    pub deployments: Vec<ErsDeployment>,
+
+    #[serde(skip)]
+    last_update: Option<(u32, u8)>,
+    #[serde(skip)]
+    deploying: bool,
+    #[serde(skip)]
+    crossed_line: bool,
+}
+
+impl ErsUsage {
+    pub fn new(car_status: &CarStatusData) -> Self {
+        Self { store_energy_at_start: car_status.ers_store_energy, ..Default::default() }
+    }
+
+    /// Records an update from the car status packet, taken at the given lap time and distance.
+    /// Deployment can only be placed on the lap once the lap distance is known.
+    /// The per-lap counters reset at the line, which the car status packet can show before the
+    /// lap data does. Updates after that are ignored, so the lap keeps its end-of-lap totals.
+    /// Likewise a lap whose first updates still show the previous lap's counters starts over.
+    pub fn record(&mut self, car_status: &CarStatusData, current_lap_time_in_ms: u32, lap_distance: Option<f32>) {
+        let counters = car_status.ers_deployed_this_lap + car_status.ers_harvested_this_lap_mgu_k + car_status.ers_harvested_this_lap_mgu_h;
+        match self.last_update {
+            // a flashback can also take the counters back, but rewinds the lap time with them
+            Some((last_time, _)) if current_lap_time_in_ms < last_time => self.crossed_line = false,
+            Some(_) if counters < self.deployed + self.harvested_mgu_k + self.harvested_mgu_h => {
+                if current_lap_time_in_ms < LINE_RESET_WINDOW_IN_MS {
+                    // the lap's first updates still carried the previous lap's counters
+                    *self = Self { store_energy_at_start: self.store_energy_at_start, ..Default::default() };
+                } else {
+                    self.crossed_line = true;
+                }
+            }
+            _ => {}
+        }
+        if self.crossed_line {
+            return;
+        }
+
+        if let Some((last_time, last_mode)) = self.last_update {
+            // time running backwards means a flashback, which isn't counted
+            if let Some(elapsed) = current_lap_time_in_ms.checked_sub(last_time) {
+                if let Some(mode_time) = self.time_in_mode_in_ms.get_mut(last_mode as usize) {
+                    *mode_time += elapsed;
+                }
+            }
+        }
+        self.last_update = Some((current_lap_time_in_ms, car_status.ers_deploy_mode));
+
+        let deployed = car_status.ers_deployed_this_lap - self.deployed;
+        match lap_distance {
+            Some(lap_distance) if deployed > 0.0 => match self.deployments.last_mut() {
+                Some(deployment) if self.deploying => {
+                    deployment.end_distance = lap_distance;
+                    deployment.energy += deployed;
+                }
+                _ => {
+                    self.deployments.push(ErsDeployment { start_distance: lap_distance, end_distance: lap_distance, energy: deployed });
+                    self.deploying = true;
+                }
+            },
+            _ => self.deploying = false,
+        }
+
+        self.store_energy = car_status.ers_store_energy;
+        self.harvested_mgu_k = car_status.ers_harvested_this_lap_mgu_k;
+        self.harvested_mgu_h = car_status.ers_harvested_this_lap_mgu_h;
+        self.deployed = car_status.ers_deployed_this_lap;
+    }
+
+    /// Whether the counters have reset for the next lap.
+    pub fn crossed_line(&self) -> bool {
+        self.crossed_line
+    }
+
+    /// Energy harvested minus energy deployed over the lap (J).
+    pub fn energy_balance(&self) -> f32 {
+        self.harvested_mgu_k + self.harvested_mgu_h - self.deployed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn car_status(deployed: f32, harvested: f32) -> CarStatusData {
+        // SAFETY: the packet is plain numbers, for which all zeroes is valid
+        let mut car_status: CarStatusData = unsafe { std::mem::zeroed() };
+        car_status.ers_deployed_this_lap = deployed;
+        car_status.ers_harvested_this_lap_mgu_k = harvested;
+        car_status
+    }
+
+    #[test]
+    fn keeps_end_of_lap_totals_when_the_counters_reset_first() {
+        let mut ers = ErsUsage::new(&car_status(0.0, 0.0));
+        ers.record(&car_status(1000.0, 500.0), 60_000, Some(3000.0));
+        ers.record(&car_status(2000.0, 900.0), 90_000, Some(5200.0));
+        // the car status packet has seen the line before the lap data
+        ers.record(&car_status(0.0, 10.0), 90_020, Some(5210.0));
+
+        assert!(ers.crossed_line());
+        assert_eq!((ers.deployed, ers.harvested_mgu_k), (2000.0, 900.0));
+    }
+
+    #[test]
+    fn flashbacks_take_the_counters_back_with_them() {
+        let mut ers = ErsUsage::new(&car_status(0.0, 0.0));
+        ers.record(&car_status(2000.0, 900.0), 60_000, Some(3000.0));
+        ers.record(&car_status(1000.0, 500.0), 59_000, Some(2950.0));
+        ers.record(&car_status(1000.0, 500.0), 40_000, Some(2000.0));
+
+        assert!(!ers.crossed_line());
+        assert_eq!((ers.deployed, ers.harvested_mgu_k), (1000.0, 500.0));
+    }
+
+    #[test]
+    fn starts_over_if_the_lap_began_with_the_previous_counters() {
+        let mut ers = ErsUsage::new(&car_status(2000.0, 900.0));
+        ers.record(&car_status(2000.0, 900.0), 10, Some(1.0));
+        ers.record(&car_status(0.0, 5.0), 30, Some(2.0));
+
+        assert!(!ers.crossed_line());
+        assert_eq!((ers.deployed, ers.harvested_mgu_k), (0.0, 5.0));
+    }
+}
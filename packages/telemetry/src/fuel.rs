@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{lap_time::LapTime, session::Lap, CarStatusData};
+
+/// Lap time (s) each kilogram of fuel is assumed to cost when no setting has been saved
+pub const DEFAULT_SECONDS_PER_KG: f32 = 0.03;
+
+/// Fuel used over a single lap.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FuelUsage {
+    /// Fuel in the tank when the lap started (kg)
+    pub fuel_at_start: f32,
+    /// Fuel in the tank at the latest update (kg)
+    pub fuel_in_tank: f32,
+    /// Laps of fuel remaining, as shown on the MFD
+    pub fuel_remaining_laps: f32,
+    /// Fuel burnt under each mix (lean, standard, rich, max) (kg)
+    pub burn_by_mix: [f32; 4],
+}
+
+impl FuelUsage {
+    pub fn new(car_status: &CarStatusData) -> Self {
+        Self {
+            fuel_at_start: car_status.fuel_in_tank,
+            fuel_in_tank: car_status.fuel_in_tank,
+            fuel_remaining_laps: car_status.fuel_remaining_laps,
+            burn_by_mix: [0.0; 4],
+        }
+    }
+
+    /// Adds the fuel burnt since the last update to the mix currently selected.
+    /// Fuel coming back after a flashback is taken off that mix again.
+    pub fn record(&mut self, car_status: &CarStatusData) {
+        let burnt = self.fuel_in_tank - car_status.fuel_in_tank;
+        if let Some(mix_burn) = self.burn_by_mix.get_mut(car_status.fuel_mix as usize) {
+            *mix_burn = (*mix_burn + burnt).max(0.0);
+        }
+
+        self.fuel_in_tank = car_status.fuel_in_tank;
+        self.fuel_remaining_laps = car_status.fuel_remaining_laps;
+    }
+
+    /// Total fuel burnt over the lap (kg).
+    pub fn fuel_used(&self) -> f32 {
+        self.burn_by_mix.iter().sum()
+    }
+
+    /// The mix most of the lap's fuel was burnt under.
+    pub fn main_fuel_mix(&self) -> u8 {
+        (0..4).max_by(|&a, &b| self.burn_by_mix[a].total_cmp(&self.burn_by_mix[b])).unwrap_or(1) as u8
+    }
+
+    /// Average fuel mass carried over the lap (kg).
+    pub fn mean_fuel_mass(&self) -> f32 {
+        (self.fuel_at_start + self.fuel_in_tank) / 2.0
+    }
+}
+
+/// Takes the cost of the fuel carried over a lap off its time, so that laps at
+/// different fuel loads can be compared.
+pub fn fuel_corrected_lap_time(lap_time: LapTime, fuel: &FuelUsage, seconds_per_kg: f32) -> LapTime {
+    let correction_in_ms = (fuel.mean_fuel_mass() * seconds_per_kg * 1000.0).round() as u32;
+    LapTime::from_millis(lap_time.as_millis().saturating_sub(correction_in_ms))
+}
+
+/// Running average of the fuel burnt per lap under a single fuel mix.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FuelConsumption {
+    pub fuel_mix: u8,
+    pub laps: u32,
+    /// Average fuel burnt per lap (kg)
+    pub average_fuel_used: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct FuelModel {
+    /// Lap time (s) each kilogram of fuel costs, used for fuel-corrected lap times
+    pub seconds_per_kg: f32,
+    pub consumption: Vec<FuelConsumption>,
+}
+
+impl Default for FuelModel {
+    fn default() -> Self {
+        Self { seconds_per_kg: DEFAULT_SECONDS_PER_KG, consumption: Vec::new() }
+    }
+}
+
+impl FuelModel {
+    /// Folds a finished lap into the average for the mix it was mostly run on.
+    pub fn record_lap(&mut self, lap: &Lap) {
+        let Some(fuel) = &lap.fuel else { return };
+        let fuel_used = fuel.fuel_used();
+        if fuel_used <= 0.0 {
+            return;
+        }
+
+        let fuel_mix = fuel.main_fuel_mix();
+        match self.consumption.iter_mut().find(|c| c.fuel_mix == fuel_mix) {
+            Some(consumption) => {
+                consumption.laps += 1;
+                consumption.average_fuel_used += (fuel_used - consumption.average_fuel_used) / consumption.laps as f32;
+            }
+            None => self.consumption.push(FuelConsumption { fuel_mix, laps: 1, average_fuel_used: fuel_used }),
+        }
+    }
+
+    /// Average fuel burnt per lap across all mixes (kg).
+    pub fn average_fuel_used(&self) -> Option<f32> {
+        let laps: u32 = self.consumption.iter().map(|c| c.laps).sum();
+        let total: f32 = self.consumption.iter().map(|c| c.average_fuel_used * c.laps as f32).sum();
+        (laps > 0).then(|| total / laps as f32)
+    }
+
+    /// Fuel-corrected time of a finished lap, if its fuel was recorded.
+    pub fn corrected_lap_time(&self, lap: &Lap) -> Option<LapTime> {
+        lap.fuel.as_ref().map(|fuel| fuel_corrected_lap_time(lap.lap_time, fuel, self.seconds_per_kg))
+    }
+}
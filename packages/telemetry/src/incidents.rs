@@ -0,0 +1,311 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{damage::{Collision, CollisionSeverity}, EventDataDetails, InfringementType, LapData, PacketEventData, PenaltyType};
+
+/// What happened in an incident.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum IncidentKind {
+    #[serde(rename_all = "camelCase")]
+    Penalty {
+        penalty_type: PenaltyType,
+        infringement_type: InfringementType,
+        /// Time added (s), 255 if not a time penalty
+        time: u8,
+        places_gained: u8,
+    },
+    DriveThroughServed,
+    StopGoServed,
+    Retirement,
+    Overtake,
+    #[serde(rename_all = "camelCase")]
+    Collision {
+        severity: CollisionSeverity,
+        damage_increase: u32,
+        peak_g_force: f32,
+    },
+}
+
+/// A single entry in the incident timeline.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Incident {
+    pub kind: IncidentKind,
+    /// The car penalised, retiring or overtaking
+    pub vehicle_idx: u8,
+    /// The other car involved, if any (the car overtaken, for overtakes)
+    pub other_vehicle_idx: Option<u8>,
+    /// Lap the incident happened on (1-based lap number), if known
+    pub lap_number: Option<u8>,
+    /// Where `vehicle_idx` was around the lap, if known
+    pub lap_distance: Option<f32>,
+    pub session_time: f32,
+}
+
+impl Incident {
+    pub fn involves(&self, car_index: u8) -> bool {
+        self.vehicle_idx == car_index || self.other_vehicle_idx == Some(car_index)
+    }
+}
+
+/// Every penalty, retirement and overtake in a session, in the order they happened.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IncidentLog {
+    pub incidents: Vec<Incident>,
+}
+
+impl IncidentLog {
+    /// Adds the event to the timeline if it is an incident. `lap_data` is the latest
+    /// lap data of every car, used to place the incident on track.
+    pub fn record(&mut self, event: &PacketEventData, lap_data: &[LapData]) -> Option<Incident> {
+        let (kind, vehicle_idx, other_vehicle_idx, penalty_lap) = match event.event_details {
+            EventDataDetails::Penalty(p) => (
+                IncidentKind::Penalty {
+                    penalty_type: p.penalty_type,
+                    infringement_type: p.infringement_type,
+                    time: p.time,
+                    places_gained: p.places_gained,
+                },
+                p.vehicle_idx,
+                // 255 means no other car was involved
+                Some(p.other_vehicle_idx).filter(|&idx| idx != 255),
+                Some(p.lap_num),
+            ),
+            EventDataDetails::DriveThroughPenaltyServed(p) => (IncidentKind::DriveThroughServed, p.vehicle_idx, None, None),
+            EventDataDetails::StopGoPenaltyServed(p) => (IncidentKind::StopGoServed, p.vehicle_idx, None, None),
+            EventDataDetails::Retirement(p) => (IncidentKind::Retirement, p.vehicle_idx, None, None),
+            EventDataDetails::Overtake(p) => {
+                (IncidentKind::Overtake, p.overtaking_vehicle_idx, Some(p.being_overtaken_vehicle_idx), None)
+            }
+            _ => return None,
+        };
+
+        let car_lap = lap_data.get(vehicle_idx as usize);
+        let incident = Incident {
+            kind,
+            vehicle_idx,
+            other_vehicle_idx,
+            lap_number: penalty_lap.or(car_lap.map(|lap| lap.current_lap_num)).filter(|&n| n > 0),
+            lap_distance: car_lap.map(|lap| lap.lap_distance),
+            session_time: event.header.session_time,
+        };
+        self.incidents.push(incident);
+        Some(incident)
+    }
+
+    /// Adds a collision detected from a car's damage to the timeline.
+    pub fn record_collision(&mut self, car_index: u8, collision: &Collision) -> Incident {
+        let incident = Incident {
+            kind: IncidentKind::Collision {
+                severity: collision.severity,
+                damage_increase: collision.damage_increase,
+                peak_g_force: collision.peak_g_force,
+            },
+            vehicle_idx: car_index,
+            other_vehicle_idx: None,
+            lap_number: Some(collision.lap_number),
+            lap_distance: Some(collision.lap_distance),
+            session_time: collision.session_time,
+        };
+        self.incidents.push(incident);
+        incident
+    }
+
+    /// Incidents involving a car on the given lap (1-based lap number).
+    pub fn for_lap(&self, car_index: u8, lap_number: u8) -> impl Iterator<Item = &Incident> {
+        self.incidents
+            .iter()
+            .filter(move |incident| incident.involves(car_index) && incident.lap_number == Some(lap_number))
+    }
+
+    pub fn penalties(&self, car_index: u8) -> impl Iterator<Item = &Incident> {
+        self.incidents
+            .iter()
+            .filter(move |incident| incident.vehicle_idx == car_index && matches!(incident.kind, IncidentKind::Penalty { .. }))
+    }
+}
+
+/// Why the game deleted a lap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InvalidationReason {
+    CornerCutting,
+    RunningWide,
+    WallRiding,
+    FlashbackUsed,
+    ResetToTrack,
+    /// Invalidated by a penalty for some other infringement
+    Penalty,
+}
+
+impl From<InfringementType> for InvalidationReason {
+    fn from(infringement_type: InfringementType) -> Self {
+        match infringement_type {
+            InfringementType::LapInvalidatedCornerCutting
+            | InfringementType::CornerCuttingGainedTime
+            | InfringementType::CornerCuttingOvertakeSingle
+            | InfringementType::CornerCuttingOvertakeMultiple => InvalidationReason::CornerCutting,
+            InfringementType::LapInvalidatedRunningWide
+            | InfringementType::CornerCuttingRanWideGaintedTimeMinor
+            | InfringementType::CornerCuttingRanWideGaintedTimeSignificant
+            | InfringementType::CornerCuttingRanWideGaintedTimeExtreme => InvalidationReason::RunningWide,
+            InfringementType::LapInvalidatedWallRiding => InvalidationReason::WallRiding,
+            InfringementType::LapInvalidatedFlashbackUsed => InvalidationReason::FlashbackUsed,
+            InfringementType::LapInvalidatedResetToTrack => InvalidationReason::ResetToTrack,
+            _ => InvalidationReason::Penalty,
+        }
+    }
+}
+
+/// A lap deleted by the game, with why and where.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LapInvalidation {
+    /// The lap deleted (1-based lap number)
+    pub lap_number: u8,
+    pub reason: InvalidationReason,
+    pub penalty_type: PenaltyType,
+    pub infringement_type: InfringementType,
+    /// Where the car was when the lap was deleted, if it happened on this lap
+    pub lap_distance: Option<f32>,
+    pub session_time: f32,
+}
+
+impl LapInvalidation {
+    /// Laps deleted by a penalty. Lap invalidation infringements delete the lap they
+    /// happened on even when the penalty itself is only a warning.
+    pub fn from_incident(incident: &Incident) -> Vec<Self> {
+        let IncidentKind::Penalty { penalty_type, infringement_type, .. } = incident.kind else {
+            return Vec::new();
+        };
+        let Some(lap_number) = incident.lap_number else {
+            return Vec::new();
+        };
+
+        let invalidates_lap = matches!(
+            infringement_type,
+            InfringementType::LapInvalidatedCornerCutting
+                | InfringementType::LapInvalidatedRunningWide
+                | InfringementType::LapInvalidatedWallRiding
+                | InfringementType::LapInvalidatedFlashbackUsed
+                | InfringementType::LapInvalidatedResetToTrack
+        );
+        let laps: &[i16] = match penalty_type {
+            PenaltyType::ThisLapInvalidated => &[0],
+            PenaltyType::ThisAndNextLapInvalidated => &[0, 1],
+            PenaltyType::ThisAndPreviousLapInvalidated | PenaltyType::ThisAndPreviousLapInvalidatedWithoutReason => &[-1, 0],
+            _ if invalidates_lap => &[0],
+            _ => &[],
+        };
+
+        laps.iter()
+            .filter_map(|&offset| u8::try_from(lap_number as i16 + offset).ok().filter(|&n| n > 0))
+            .map(|affected_lap| Self {
+                lap_number: affected_lap,
+                reason: infringement_type.into(),
+                penalty_type,
+                infringement_type,
+                lap_distance: incident.lap_distance.filter(|_| affected_lap == lap_number),
+                session_time: incident.session_time,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn penalty(penalty_type: PenaltyType, infringement_type: InfringementType, lap_number: Option<u8>) -> Incident {
+        Incident {
+            kind: IncidentKind::Penalty { penalty_type, infringement_type, time: 255, places_gained: 255 },
+            vehicle_idx: 0,
+            other_vehicle_idx: None,
+            lap_number,
+            lap_distance: Some(1234.0),
+            session_time: 600.0,
+        }
+    }
+
+    fn deleted_laps(incident: &Incident) -> Vec<u8> {
+        LapInvalidation::from_incident(incident).iter().map(|invalidation| invalidation.lap_number).collect()
+    }
+
+    #[test]
+    fn deletes_this_lap() {
+        let incident = penalty(PenaltyType::ThisLapInvalidated, InfringementType::LapInvalidatedCornerCutting, Some(5));
+        let invalidations = LapInvalidation::from_incident(&incident);
+
+        assert_eq!(invalidations.len(), 1);
+        assert_eq!(invalidations[0].lap_number, 5);
+        assert_eq!(invalidations[0].reason, InvalidationReason::CornerCutting);
+        assert_eq!(invalidations[0].lap_distance, Some(1234.0));
+        assert_eq!(invalidations[0].session_time, 600.0);
+    }
+
+    #[test]
+    fn deletes_this_and_the_next_lap() {
+        let incident = penalty(PenaltyType::ThisAndNextLapInvalidated, InfringementType::LapInvalidatedRunningWide, Some(5));
+        let invalidations = LapInvalidation::from_incident(&incident);
+
+        assert_eq!(deleted_laps(&incident), vec![5, 6]);
+        assert!(invalidations.iter().all(|i| i.reason == InvalidationReason::RunningWide));
+        // the car hasn't been anywhere on the next lap yet
+        assert_eq!(invalidations[1].lap_distance, None);
+    }
+
+    #[test]
+    fn deletes_this_and_the_previous_lap() {
+        for penalty_type in [PenaltyType::ThisAndPreviousLapInvalidated, PenaltyType::ThisAndPreviousLapInvalidatedWithoutReason] {
+            let incident = penalty(penalty_type, InfringementType::LapInvalidatedWallRiding, Some(5));
+            let invalidations = LapInvalidation::from_incident(&incident);
+
+            assert_eq!(deleted_laps(&incident), vec![4, 5]);
+            assert_eq!(invalidations[0].lap_distance, None);
+            assert_eq!(invalidations[1].lap_distance, Some(1234.0));
+        }
+    }
+
+    #[test]
+    fn never_deletes_a_lap_before_the_first() {
+        let incident = penalty(PenaltyType::ThisAndPreviousLapInvalidated, InfringementType::LapInvalidatedResetToTrack, Some(1));
+        assert_eq!(deleted_laps(&incident), vec![1]);
+    }
+
+    #[test]
+    fn warnings_for_lap_invalidation_infringements_delete_the_lap() {
+        let incident = penalty(PenaltyType::Warning, InfringementType::LapInvalidatedFlashbackUsed, Some(3));
+        let invalidations = LapInvalidation::from_incident(&incident);
+
+        assert_eq!(deleted_laps(&incident), vec![3]);
+        assert_eq!(invalidations[0].reason, InvalidationReason::FlashbackUsed);
+        assert_eq!(invalidations[0].penalty_type, PenaltyType::Warning);
+    }
+
+    #[test]
+    fn other_penalties_keep_the_lap() {
+        for penalty_type in [PenaltyType::TimePenalty, PenaltyType::DriveThrough, PenaltyType::Warning] {
+            let incident = penalty(penalty_type, InfringementType::CornerCuttingGainedTime, Some(3));
+            assert!(LapInvalidation::from_incident(&incident).is_empty(), "{:?}", penalty_type);
+        }
+    }
+
+    #[test]
+    fn needs_a_penalty_on_a_known_lap() {
+        let incident = penalty(PenaltyType::ThisLapInvalidated, InfringementType::LapInvalidatedCornerCutting, None);
+        assert!(LapInvalidation::from_incident(&incident).is_empty());
+
+        let overtake = Incident { kind: IncidentKind::Overtake, ..penalty(PenaltyType::ThisLapInvalidated, InfringementType::LapInvalidatedCornerCutting, Some(3)) };
+        assert!(LapInvalidation::from_incident(&overtake).is_empty());
+    }
+
+    #[test]
+    fn penalties_for_other_infringements_are_put_down_to_the_penalty() {
+        let incident = penalty(PenaltyType::ThisLapInvalidated, InfringementType::PitLaneSpeeding, Some(8));
+        let invalidations = LapInvalidation::from_incident(&incident);
+
+        assert_eq!(deleted_laps(&incident), vec![8]);
+        assert_eq!(invalidations[0].reason, InvalidationReason::Penalty);
+    }
+}
@@ -0,0 +1,158 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+/// A full lap time, stored in milliseconds.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct LapTime(u32);
+
+/// A sector time, stored in milliseconds.
+///
+/// The game splits sector times into a millisecond part (`u16`) and a whole minute part (`u8`),
+/// so anything over 65.535 seconds only fits once both parts are combined.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SectorTime(u32);
+
+impl SectorTime {
+    /// Combines the millisecond and whole minute parts sent by the game.
+    pub fn from_parts(time_in_ms: u16, time_minutes: u8) -> Self {
+        Self(time_minutes as u32 * 60_000 + time_in_ms as u32)
+    }
+}
+
+impl LapTime {
+    /// Derives the remaining sector from a full lap time and the two sectors before it.
+    /// Returns `None` if either sector is missing or together they exceed the lap time.
+    pub fn remaining_sector(&self, sector1: SectorTime, sector2: SectorTime) -> Option<SectorTime> {
+        if sector1.is_zero() || sector2.is_zero() {
+            return None;
+        }
+        self.0.checked_sub(sector1.0 + sector2.0).map(SectorTime)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TimeParseError {
+    InvalidFormat(String),
+}
+
+impl fmt::Display for TimeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeParseError::InvalidFormat(s) => write!(f, "Invalid time \"{}\", expected m:ss.mmm", s),
+        }
+    }
+}
+
+/// Parses `m:ss.mmm`, or `ss.mmm` for times under a minute, into milliseconds.
+fn parse_millis(s: &str) -> Result<u32, TimeParseError> {
+    let err = || TimeParseError::InvalidFormat(s.to_string());
+
+    let (minutes, rest) = match s.split_once(':') {
+        Some((minutes, rest)) => (minutes.parse::<u32>().map_err(|_| err())?, rest),
+        None => (0, s),
+    };
+    let (seconds, millis) = rest.split_once('.').ok_or_else(err)?;
+    if seconds.is_empty() || millis.len() != 3 {
+        return Err(err());
+    }
+
+    let seconds = seconds.parse::<u32>().map_err(|_| err())?;
+    let millis = millis.parse::<u32>().map_err(|_| err())?;
+    if seconds >= 60 && s.contains(':') {
+        return Err(err());
+    }
+
+    minutes
+        .checked_mul(60_000)
+        .and_then(|total| total.checked_add(seconds.checked_mul(1_000)?))
+        .and_then(|total| total.checked_add(millis))
+        .ok_or_else(err)
+}
+
+macro_rules! impl_time {
+    ($time:ident) => {
+        impl $time {
+            pub fn from_millis(millis: u32) -> Self {
+                Self(millis)
+            }
+
+            pub fn as_millis(&self) -> u32 {
+                self.0
+            }
+
+            pub fn is_zero(&self) -> bool {
+                self.0 == 0
+            }
+        }
+
+        impl From<u32> for $time {
+            fn from(millis: u32) -> Self {
+                Self(millis)
+            }
+        }
+
+        impl fmt::Display for $time {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let minutes = self.0 / 60_000;
+                let seconds = (self.0 % 60_000) / 1_000;
+                let millis = self.0 % 1_000;
+                write!(f, "{}:{:02}.{:03}", minutes, seconds, millis)
+            }
+        }
+
+        impl FromStr for $time {
+            type Err = TimeParseError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                parse_millis(s.trim()).map(Self)
+            }
+        }
+    };
+}
+
+impl_time!(LapTime);
+impl_time!(SectorTime);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_and_parses_round_trip() {
+        for millis in [0, 999, 59_999, 83_456, 5_999_999] {
+            let time = LapTime::from_millis(millis);
+            assert_eq!(time.to_string().parse::<LapTime>(), Ok(time));
+        }
+        assert_eq!(LapTime::from_millis(83_456).to_string(), "1:23.456");
+        assert_eq!("23.456".parse::<SectorTime>(), Ok(SectorTime::from_millis(23_456)));
+    }
+
+    #[test]
+    fn sectors_over_65_seconds_keep_their_minutes() {
+        let sector = SectorTime::from_parts(5_500, 1);
+        assert_eq!(sector.as_millis(), 65_500);
+        assert_eq!(sector.to_string(), "1:05.500");
+
+        let sector = SectorTime::from_parts(12_345, 2);
+        assert_eq!(sector.as_millis(), 132_345);
+        assert_eq!(sector.to_string().parse::<SectorTime>(), Ok(sector));
+    }
+
+    #[test]
+    fn rejects_malformed_times() {
+        for s in ["", "1:23", "1:23.45", "1:60.000", "a:23.456", "1:23.4567"] {
+            assert_eq!(s.parse::<LapTime>(), Err(TimeParseError::InvalidFormat(s.to_string())));
+        }
+    }
+
+    #[test]
+    fn rejects_times_that_overflow() {
+        assert_eq!("4294967.295".parse::<LapTime>(), Ok(LapTime::from_millis(u32::MAX)));
+        for s in ["4294967.296", "4294968.000", "71582:47.296", "71583:00.000"] {
+            assert_eq!(s.parse::<LapTime>(), Err(TimeParseError::InvalidFormat(s.to_string())));
+        }
+    }
+}
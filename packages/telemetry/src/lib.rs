@@ -1,5 +1,23 @@
 pub mod packet;
 pub mod session;
+pub mod session_info;
 pub mod assists;
+pub mod delta;
+pub mod records;
+pub mod lap_time;
+pub mod analysis;
+pub mod tyres;
+pub mod fuel;
+pub mod ers;
+pub mod strategy;
+pub mod undercut;
+pub mod pit_stops;
+pub mod weather;
+pub mod weekend;
+pub mod classification;
+pub mod incidents;
+pub mod damage;
+pub mod power_unit;
+pub mod setup;
 
 pub use packet::*;
\ No newline at end of file
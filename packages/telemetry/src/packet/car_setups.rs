@@ -15,7 +15,7 @@ pub struct PacketCarSetupData {
     /// Header
     pub header: super::header::PacketHeader,
     /// Data for all cars on track
-    car_setups: [CarSetupData; 22],
+    pub car_setups: [CarSetupData; 22],
 }
 
 impl FromBytes for PacketCarSetupData {
@@ -40,7 +40,7 @@ impl super::PacketAttributes for PacketCarSetupData {
 
 #[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
 #[repr(C, packed)]
-struct CarSetupData {
+pub struct CarSetupData {
     /// Front wing aero
     pub front_wing: u8,
     /// Rear wing aero
@@ -1,5 +1,6 @@
 use super::{PacketAttributes, FromBytes, PacketError};
 use super::header::PacketHeader;
+use crate::lap_time::{LapTime, SectorTime};
 
 /// # Lap Data Packet
 /// The lap data packet gives details of all the cars in the session.
@@ -98,3 +99,21 @@ pub struct LapData {
     /// Whether the car should serve a penalty at this stop
     pub pit_stop_should_serve_pen: bool,
 }
+
+impl LapData {
+    pub fn last_lap_time(&self) -> LapTime {
+        LapTime::from_millis(self.last_lap_time_in_ms)
+    }
+
+    pub fn current_lap_time(&self) -> LapTime {
+        LapTime::from_millis(self.current_lap_time_in_ms)
+    }
+
+    pub fn sector1_time(&self) -> SectorTime {
+        SectorTime::from_parts(self.sector1_time_in_ms, self.sector1_time_minutes)
+    }
+
+    pub fn sector2_time(&self) -> SectorTime {
+        SectorTime::from_parts(self.sector2_time_in_ms, self.sector2_time_minutes)
+    }
+}
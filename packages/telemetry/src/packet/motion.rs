@@ -82,3 +82,39 @@ pub struct CarMotionData {
     /// Roll angle in radians
     pub m_roll: f32,
 }
+
+#[derive(Default, Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct JSONCarMotionData {
+    /// World space X position - metres
+    pub world_position_x: f32,
+    /// World space Y position
+    pub world_position_y: f32,
+    /// World space Z position
+    pub world_position_z: f32,
+    /// Lateral G-Force component
+    pub g_force_lateral: f32,
+    /// Longitudinal G-Force component
+    pub g_force_longitudinal: f32,
+    /// Vertical G-Force component
+    pub g_force_vertical: f32,
+    /// Yaw angle in radians
+    pub yaw: f32,
+    /// Current lap time (ms)
+    pub current_lap_time_in_ms: u32
+}
+
+impl JSONCarMotionData {
+    pub fn new(value: CarMotionData, current_lap_time: u32) -> Self {
+        Self {
+            world_position_x: value.world_position_x,
+            world_position_y: value.world_position_y,
+            world_position_z: value.world_position_z,
+            g_force_lateral: value.g_force_lateral,
+            g_force_longitudinal: value.g_force_longitudinal,
+            g_force_vertical: value.g_force_vertical,
+            yaw: value.m_yaw,
+            current_lap_time_in_ms: current_lap_time
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use serde_big_array::BigArray;
 
 use super::{PacketAttributes, FromBytes};
+use crate::lap_time::{LapTime, SectorTime};
 
 /// # Session History Packet
 ///
@@ -83,9 +84,27 @@ pub struct LapHistoryData {
 #[repr(C, packed)]
 pub struct TyreStintHistoryData {
     /// Lap the tyre usage ends on (255 if current tyre)
-    end_lap: u8,
+    pub end_lap: u8,
     /// Actual tyres used
-    tyre_actual_compound: u8,
+    pub tyre_actual_compound: u8,
     /// Visual tyres used
-    tyre_visual_compound: u8,
+    pub tyre_visual_compound: u8,
+}
+
+impl LapHistoryData {
+    pub fn lap_time(&self) -> LapTime {
+        LapTime::from_millis(self.lap_time_in_ms)
+    }
+
+    pub fn sector_1_time(&self) -> SectorTime {
+        SectorTime::from_parts(self.sector_1_time_in_ms, self.sector_1_time_minutes)
+    }
+
+    pub fn sector_2_time(&self) -> SectorTime {
+        SectorTime::from_parts(self.sector_2_time_in_ms, self.sector_2_time_minutes)
+    }
+
+    pub fn sector_3_time(&self) -> SectorTime {
+        SectorTime::from_parts(self.sector_3_time_in_ms, self.sector_3_time_minutes)
+    }
 }
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+
+use crate::PacketLapData;
+
+/// Tyres on a car at a point in time.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CarTyres {
+    pub actual_compound: u8,
+    pub visual_compound: u8,
+    pub tyres_age_laps: u8,
+}
+
+/// Average time lost by driving through the pit lane instead of around the track.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PitLoss {
+    pub average_in_ms: u32,
+    pub samples: u32,
+}
+
+impl PitLoss {
+    pub fn add_sample(&mut self, loss_in_ms: u32) {
+        self.samples += 1;
+        let average = self.average_in_ms as i64 + (loss_in_ms as i64 - self.average_in_ms as i64) / self.samples as i64;
+        self.average_in_ms = average as u32;
+    }
+}
+
+/// A single pass through the pit lane.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PitStop {
+    pub car_index: u8,
+    pub entry_lap: u8,
+    pub exit_lap: u8,
+    pub entry_distance: f32,
+    pub exit_distance: f32,
+    pub time_in_lane_in_ms: u16,
+    /// Time spent stationary in the box (ms)
+    pub stationary_time_in_ms: u16,
+    pub tyres_before: Option<CarTyres>,
+    pub tyres_after: Option<CarTyres>,
+    pub served_penalty: bool,
+    /// The car's last lap time before it entered the pit lane (ms)
+    pub lap_time_before_in_ms: u32,
+}
+
+impl PitStop {
+    /// Time lost by going through the pit lane: the time in the lane minus the time the car
+    /// would have taken to cover the same distance at its pace before the stop.
+    /// Returns `None` for stops that served a penalty, or when there is no pace to compare against.
+    pub fn loss_in_ms(&self, track_length: u16) -> Option<u32> {
+        if self.served_penalty || track_length == 0 || self.lap_time_before_in_ms == 0 {
+            return None;
+        }
+
+        let mut distance = self.exit_distance - self.entry_distance;
+        if distance < 0.0 {
+            distance += track_length as f32;
+        }
+        let track_time = self.lap_time_before_in_ms as f32 * distance / track_length as f32;
+        let loss = self.time_in_lane_in_ms as f32 - track_time;
+        (loss > 0.0).then(|| loss.round() as u32)
+    }
+}
+
+/// Every pit stop made by every car during a session.
+#[derive(Debug, Default, Clone)]
+pub struct PitStopLog {
+    pub stops: Vec<PitStop>,
+    in_lane: [Option<PitStop>; 22],
+}
+
+impl PitStopLog {
+    /// Follows each car's pit lane timer, logging a stop once the car leaves the lane.
+    /// `tyres` holds the latest tyres of each car, by car index.
+    /// Returns the stops completed by this update.
+    pub fn record(&mut self, lap_packet: &PacketLapData, tyres: &[CarTyres]) -> &[PitStop] {
+        let completed = self.stops.len();
+        for (car_index, (in_lane, lap_data)) in self.in_lane.iter_mut().zip(lap_packet.lap_data).enumerate() {
+            let car_tyres = tyres.get(car_index).copied();
+            if lap_data.pit_lane_timer_active {
+                let stop = in_lane.get_or_insert(PitStop {
+                    car_index: car_index as u8,
+                    entry_lap: lap_data.current_lap_num,
+                    exit_lap: lap_data.current_lap_num,
+                    entry_distance: lap_data.lap_distance,
+                    exit_distance: lap_data.lap_distance,
+                    time_in_lane_in_ms: 0,
+                    stationary_time_in_ms: 0,
+                    tyres_before: car_tyres,
+                    tyres_after: None,
+                    served_penalty: false,
+                    lap_time_before_in_ms: lap_data.last_lap_time_in_ms,
+                });
+                stop.time_in_lane_in_ms = lap_data.pit_lane_time_in_lane_in_ms;
+                stop.stationary_time_in_ms = stop.stationary_time_in_ms.max(lap_data.pit_stop_timer_in_ms);
+                stop.served_penalty |= lap_data.pit_stop_should_serve_pen;
+            } else if let Some(mut stop) = in_lane.take() {
+                stop.exit_lap = lap_data.current_lap_num;
+                stop.exit_distance = lap_data.lap_distance;
+                stop.tyres_after = car_tyres;
+                self.stops.push(stop);
+            }
+        }
+        &self.stops[completed..]
+    }
+}
@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{weekend::{EngineWear, WeekendLink}, CarDamageData};
+
+/// Wear (%) past which a component is at risk of failing.
+pub const DEFAULT_WEAR_LIMIT: f32 = 75.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Component {
+    Ice,
+    MguH,
+    MguK,
+    EnergyStore,
+    ControlElectronics,
+    Turbocharger,
+    Gearbox,
+}
+
+impl Component {
+    pub const ALL: [Component; 7] = [
+        Component::Ice,
+        Component::MguH,
+        Component::MguK,
+        Component::EnergyStore,
+        Component::ControlElectronics,
+        Component::Turbocharger,
+        Component::Gearbox,
+    ];
+}
+
+/// Power unit and gearbox wear at the end of a session.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PowerUnitSample {
+    pub weekend_link_identifier: u32,
+    pub session_link_identifier: u32,
+    /// Distance driven in the session (m)
+    pub session_distance: f32,
+    pub engine_wear: EngineWear,
+    pub gearbox: u8,
+}
+
+impl PowerUnitSample {
+    pub fn wear(&self, component: Component) -> u8 {
+        let wear = self.engine_wear;
+        match component {
+            Component::Ice => wear.ice,
+            Component::MguH => wear.mgu_h,
+            Component::MguK => wear.mgu_k,
+            Component::EnergyStore => wear.energy_store,
+            Component::ControlElectronics => wear.control_electronics,
+            Component::Turbocharger => wear.turbocharger,
+            Component::Gearbox => self.gearbox,
+        }
+    }
+}
+
+/// When a component is expected to reach the wear limit.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WearProjection {
+    pub component: Component,
+    pub wear: u8,
+    /// Wear gained per session on the current component (%)
+    pub wear_per_session: f32,
+    /// Wear gained per kilometre on the current component (%)
+    pub wear_per_km: f32,
+    pub sessions_remaining: Option<f32>,
+    pub km_remaining: Option<f32>,
+}
+
+/// Wear on the player's power unit over every linked session of a season.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PowerUnitHistory {
+    pub season_link_identifier: u32,
+    pub samples: Vec<PowerUnitSample>,
+}
+
+impl PowerUnitHistory {
+    pub fn new(season_link_identifier: u32) -> Self {
+        Self { season_link_identifier, ..Default::default() }
+    }
+
+    /// Updates the wear for the session, adding a sample the first time it is seen.
+    pub fn record(&mut self, link: &WeekendLink, session_distance: f32, damage: &CarDamageData) {
+        let sample = PowerUnitSample {
+            weekend_link_identifier: link.weekend_link_identifier,
+            session_link_identifier: link.session_link_identifier,
+            session_distance: session_distance.max(0.0),
+            engine_wear: EngineWear::from_damage(damage),
+            gearbox: damage.gear_box_damage,
+        };
+
+        match self.samples.last_mut() {
+            Some(last) if last.session_link_identifier == link.session_link_identifier => *last = sample,
+            _ => self.samples.push(sample),
+        }
+    }
+
+    /// Wear on a component after each session, with the distance driven up to then (m).
+    pub fn component_history(&self, component: Component) -> Vec<(f32, u8)> {
+        let mut distance = 0.0;
+        self.samples
+            .iter()
+            .map(|sample| {
+                distance += sample.session_distance;
+                (distance, sample.wear(component))
+            })
+            .collect()
+    }
+
+    /// Projects when the component fitted now reaches the wear limit, from the wear gained
+    /// since it was fitted. A drop in wear between sessions means it was replaced.
+    pub fn projection(&self, component: Component, wear_limit: f32) -> Option<WearProjection> {
+        let history = self.component_history(component);
+        let fitted = history.windows(2).rposition(|pair| pair[1].1 < pair[0].1).map_or(0, |i| i + 1);
+        let current = &history[fitted..];
+        let (first, last) = (current.first()?, current.last()?);
+
+        let sessions = (current.len() - 1) as f32;
+        let wear_gained = last.1.saturating_sub(first.1) as f32;
+        let km = (last.0 - first.0) / 1000.0;
+        if sessions == 0.0 || km <= 0.0 {
+            return None;
+        }
+
+        let wear_per_session = wear_gained / sessions;
+        let wear_per_km = wear_gained / km;
+        let wear_left = (wear_limit - last.1 as f32).max(0.0);
+
+        Some(WearProjection {
+            component,
+            wear: last.1,
+            wear_per_session,
+            wear_per_km,
+            sessions_remaining: (wear_per_session > 0.0).then(|| wear_left / wear_per_session),
+            km_remaining: (wear_per_km > 0.0).then(|| wear_left / wear_per_km),
+        })
+    }
+
+    pub fn projections(&self, wear_limit: f32) -> Vec<WearProjection> {
+        Component::ALL.iter().filter_map(|&c| self.projection(c, wear_limit)).collect()
+    }
+}
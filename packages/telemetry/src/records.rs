@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{lap_time::{LapTime, SectorTime}, session::Lap};
+
+/// Best lap and sector times, kept both per session and per track/assist combination.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LapRecords {
+    pub best_lap_time_in_ms: Option<LapTime>,
+    pub best_sector1_time_in_ms: Option<SectorTime>,
+    pub best_sector2_time_in_ms: Option<SectorTime>,
+    pub best_sector3_time_in_ms: Option<SectorTime>,
+    /// Sum of the best three sectors, once all of them have been set
+    pub theoretical_best_lap_time_in_ms: Option<LapTime>,
+}
+
+impl LapRecords {
+    /// Folds a finished lap into the records. Invalid laps are ignored.
+    /// Returns true if any record was improved.
+    pub fn update(&mut self, lap: &Lap) -> bool {
+        if lap.lap_invalid || lap.lap_time.is_zero() {
+            return false;
+        }
+
+        // a lap without all three sectors was never completed from the line
+        let Some(sector3_time) = lap.sector3_time() else { return false };
+
+        let mut improved = false;
+        improved |= improve(&mut self.best_lap_time_in_ms, lap.lap_time);
+        improved |= improve(&mut self.best_sector1_time_in_ms, lap.sector1_time);
+        improved |= improve(&mut self.best_sector2_time_in_ms, lap.sector2_time);
+        improved |= improve(&mut self.best_sector3_time_in_ms, sector3_time);
+
+        self.theoretical_best_lap_time_in_ms = self.theoretical_best();
+        improved
+    }
+
+    fn theoretical_best(&self) -> Option<LapTime> {
+        let sectors = [self.best_sector1_time_in_ms?, self.best_sector2_time_in_ms?, self.best_sector3_time_in_ms?];
+        Some(LapTime::from_millis(sectors.iter().map(SectorTime::as_millis).sum()))
+    }
+}
+
+fn improve<T: Ord + Copy>(record: &mut Option<T>, time: T) -> bool {
+    if record.is_some_and(|best| best <= time) {
+        return false;
+    }
+
+    *record = Some(time);
+    true
+}
@@ -1,7 +1,7 @@
 use std::collections::BTreeMap;
 
 use chrono::{DateTime, Utc};
-use crate::{assists::Assists, JSONCarTelemetryData, LapData, PacketHeader};
+use crate::{analysis::{excursions, is_clean_lap, traction_and_balance, SegmentMap, SetupLap, TrackLimits, REQUIRED_CLEAN_LAPS}, assists::Assists, delta::{self, DistanceSample, LiveDelta, ReferenceLap}, ers::ErsUsage, fuel::{FuelModel, FuelUsage}, lap_time::{LapTime, SectorTime}, records::LapRecords, session_info::SessionInfo, strategy::{plan_strategy, PitWindow, Strategy, DEFAULT_PIT_LOSS_IN_MS}, tyres::{TyreModel, DEFAULT_WEAR_THRESHOLD}, pit_stops::{PitLoss, PitStopLog}, undercut::{estimate_undercut, Field, UndercutEstimate}, weather::{TrackConditions, WeatherTimeline}, weekend::{Weekend, WeekendLink}, classification::{Classification, Participant}, incidents::{Incident, IncidentLog, LapInvalidation}, damage::{CollisionDetector, DamageState}, power_unit::PowerUnitHistory, setup::{CarSetup, SetupHistory}, JSONCarMotionData, CarDamageData, CarStatusData, JSONCarTelemetryData, LapData, MotionExData, PacketHeader, PacketLapData, PacketSessionData};
 use serde::{Serialize, Deserialize};
 
 #[derive(Default, Debug, Serialize, Deserialize)]
@@ -13,9 +13,17 @@ pub struct JSONTelemetrySession {
     pub end_date: Option<DateTime<Utc>>,
     pub total_distance: f32,
     pub weather: u8,
+    pub track_temperature: i8,
+    pub air_temperature: i8,
     pub time_of_day: u32,
     pub total_laps: u8,
     pub track_id: i8,
+    #[serde(flatten)]
+    pub info: SessionInfo,
+    #[serde(flatten)]
+    pub link: Option<WeekendLink>,
+    /// Backend ID of the weekend this session is part of
+    pub weekend_uid: Option<String>,
 }
 
 impl TryFrom<&Session> for JSONTelemetrySession {
@@ -31,9 +39,14 @@ impl TryFrom<&Session> for JSONTelemetrySession {
                 end_date: value.end_date,
                 total_distance: value.total_distance.unwrap(),
                 weather: value.weather.unwrap(),
+                track_temperature: value.track_temperature.ok_or("Track temperature is not known yet!")?,
+                air_temperature: value.air_temperature.ok_or("Air temperature is not known yet!")?,
                 time_of_day: value.time_of_day.unwrap(),
                 total_laps: value.total_laps.unwrap(),
                 track_id: value.track_id.unwrap(),
+                info: value.info.ok_or("Session info is not known yet!")?,
+                link: value.link,
+                weekend_uid: value.weekend.as_ref().and_then(|weekend| weekend.weekend_uid.clone()),
             })
         }
     }
@@ -49,15 +62,67 @@ pub struct Session {
 
     pub total_distance: Option<f32>,
     pub weather: Option<u8>,
+    pub track_temperature: Option<i8>,
+    pub air_temperature: Option<i8>,
     pub time_of_day: Option<u32>,
     pub total_laps: Option<u8>,
     pub track_id: Option<i8>,
+    pub info: Option<SessionInfo>,
     pub assists: Option<Assists>,
 
     // potentially out of scope
     // pub motion_data: Vec<CarMotionData>,
     // pub motion_ex_data: Vec<MotionExData>,
-    pub current_lap: Option<Lap>
+    pub current_lap: Option<Lap>,
+
+    /// Best stored lap for the current track and assists, used for the live delta
+    pub reference_lap: Option<ReferenceLap>,
+    /// Best sectors set during this session
+    pub session_records: LapRecords,
+    /// Best sectors ever set on the current track with the current assists
+    pub track_records: LapRecords,
+    /// Track/assist key the reference lap and track records were loaded for
+    pub records_key: Option<String>,
+
+    /// Corners of the current track, once known
+    pub segment_map: Option<SegmentMap>,
+    /// Clean laps kept until there are enough to build a segment map
+    pub clean_laps: Vec<Lap>,
+    /// Where the player leaves the track at the current track, across sessions
+    pub track_limits: Option<TrackLimits>,
+
+    /// Tyre wear and stints of the player's car
+    pub tyres: TyreModel,
+    /// Fuel consumption of the player's car
+    pub fuel: FuelModel,
+    /// The game's suggested pit window for the player
+    pub pit_window: Option<PitWindow>,
+    /// Time lost to a pit stop at the current track, once measured
+    pub pit_loss: Option<PitLoss>,
+    /// Every stop made by every car this session
+    pub pit_stops: PitStopLog,
+    pub track_length: Option<u16>,
+    /// Links the session to the rest of its weekend and season
+    pub link: Option<WeekendLink>,
+    pub weekend: Option<Weekend>,
+    /// Weather over the session and how well it was forecast
+    pub weather_timeline: WeatherTimeline,
+    /// Latest state of every car, used to judge the player's stops against their rivals
+    pub field: Field,
+    /// Drivers in the session, by car index
+    pub participants: Vec<Participant>,
+    /// Results screen, once the session has finished
+    pub classification: Option<Classification>,
+    /// Penalties, retirements and overtakes across the whole field
+    pub incidents: IncidentLog,
+    /// Every lap of the player's the game has deleted, including ones not yet started
+    pub lap_invalidations: Vec<LapInvalidation>,
+    /// Collisions the player's car has been damaged in
+    pub collision_detector: CollisionDetector,
+    /// Power unit wear over the career season, across linked sessions
+    pub power_unit: Option<PowerUnitHistory>,
+    /// Every setup the player ran this session
+    pub setups: SetupHistory,
 }
 
 impl Session {
@@ -74,19 +139,246 @@ impl Session {
             }
         }
     }
+
+    /// Key of the records that should be used for the current track and assists.
+    pub fn records_key(&self) -> Option<String> {
+        let mask = self.assists.as_ref()?.get_mask().ok()?;
+        Some(ReferenceLap::key(self.track_id?, mask))
+    }
+
+    /// Folds a finished lap into the session and track records.
+    /// Returns true if a track record was improved.
+    pub fn update_records(&mut self, finished_lap: &Lap) -> bool {
+        self.session_records.update(finished_lap);
+        self.records_key.is_some() && self.track_records.update(finished_lap)
+    }
+
+    /// Replaces the reference lap if the finished lap is a valid improvement on it.
+    /// Returns true if the reference was replaced.
+    pub fn update_reference_lap(&mut self, finished_lap: &Lap) -> bool {
+        let (Some(track_id), Some(track_length)) = (self.track_id, self.track_length) else { return false };
+        let Some(mut candidate) = ReferenceLap::from_lap(finished_lap, track_id, track_length) else { return false };
+        if let Some(segment_map) = &self.segment_map {
+            candidate.measure_braking(segment_map);
+        }
+
+        let improved = match &self.reference_lap {
+            Some(reference) if reference.track_id == candidate.track_id && reference.assists == candidate.assists => {
+                candidate.lap_time_in_ms < reference.lap_time_in_ms
+            }
+            _ => true,
+        };
+
+        if improved {
+            self.reference_lap = Some(candidate);
+        }
+        improved
+    }
+
+    /// Updates the power unit wear for this session. Only career saves carry the
+    /// power unit from one session to the next.
+    pub fn record_power_unit(&mut self, damage: &CarDamageData) {
+        let is_career = self.info.and_then(|info| info.game_mode).is_some_and(|mode| mode.is_career());
+        let (Some(link), Some(power_unit)) = (self.link, &mut self.power_unit) else { return };
+        if is_career {
+            power_unit.record(&link, self.total_distance.unwrap_or_default(), damage);
+        }
+    }
+
+    /// Snapshots the player's setup if it has changed. Returns true if it had.
+    pub fn record_setup(&mut self, setup: CarSetup) -> bool {
+        let Some(lap) = &mut self.current_lap else { return false };
+        lap.setup = Some(setup);
+        self.setups.record(lap.lap_number + 1, setup)
+    }
+
+    /// Summarises a finished lap for comparing setups. Laps the tyres were changed on are
+    /// left out, as their wear belongs to neither set.
+    pub fn setup_lap(&self, finished_lap: &Lap) -> Option<SetupLap> {
+        let tyres = self.tyres.lap(finished_lap.lap_number + 1)?;
+        let (actual_compound, _) = self.tyres.compound?;
+        let balance = self.segment_map.as_ref().and_then(|map| traction_and_balance(finished_lap, map));
+
+        SetupLap::from_lap(
+            finished_lap,
+            self.track_id?,
+            actual_compound,
+            self.fuel.corrected_lap_time(finished_lap),
+            Some(tyres),
+            balance.as_ref(),
+        )
+    }
+
+    /// Adds the finished lap's excursions to the track's per-corner statistics.
+    /// Returns true if the lap had telemetry to check.
+    pub fn update_track_limits(&mut self, finished_lap: &Lap) -> bool {
+        let Some(track_id) = self.track_id else { return false };
+        if finished_lap.car_telemetry.is_empty() {
+            return false;
+        }
+
+        let excursions = excursions(finished_lap, self.segment_map.as_ref(), self.reference_lap.as_ref());
+        self.track_limits
+            .get_or_insert_with(|| TrackLimits::new(track_id))
+            .record_lap(&excursions);
+        true
+    }
+
+    /// Keeps clean laps until enough have been collected to detect the track's corners.
+    /// Returns true once a new segment map has been built.
+    pub fn collect_segment_lap(&mut self, lap: Lap) -> bool {
+        if self.segment_map.is_some() || !is_clean_lap(&lap) {
+            return false;
+        }
+        let Some(track_id) = self.track_id else { return false };
+
+        self.clean_laps.push(lap);
+        if self.clean_laps.len() < REQUIRED_CLEAN_LAPS {
+            return false;
+        }
+
+        self.segment_map = SegmentMap::detect(track_id, &self.clean_laps);
+        self.clean_laps.clear();
+        self.segment_map.is_some()
+    }
+
+    /// Measures the reference lap's braking if it was set before the track's corners were known.
+    /// Returns true if the reference changed and should be saved again.
+    pub fn measure_reference_braking(&mut self) -> bool {
+        match (&mut self.reference_lap, &self.segment_map) {
+            (Some(reference), Some(segment_map)) if reference.track_id == segment_map.track_id => reference.measure_braking(segment_map),
+            _ => false,
+        }
+    }
+
+    /// Laps left on the current tyres before the most worn one passes the default wear threshold.
+    pub fn tyre_laps_remaining(&self) -> Option<f32> {
+        self.tyres.laps_remaining(DEFAULT_WEAR_THRESHOLD)
+    }
+
+    /// Measured time lost to a pit stop on the current track, or a default until one has been seen.
+    pub fn pit_loss_in_ms(&self) -> u32 {
+        self.pit_loss.map_or(DEFAULT_PIT_LOSS_IN_MS, |loss| loss.average_in_ms)
+    }
+
+    /// Logs pit stops as cars leave the pit lane, folding each one into the track's pit loss.
+    /// Returns true if the pit loss changed.
+    pub fn update_pit_stops(&mut self, lap_packet: &PacketLapData) -> bool {
+        let completed = self.pit_stops.record(lap_packet, &self.field.tyres);
+        let Some(track_length) = self.track_length else { return false };
+
+        let mut changed = false;
+        for loss in completed.iter().filter_map(|stop| stop.loss_in_ms(track_length)) {
+            self.pit_loss.get_or_insert_with(PitLoss::default).add_sample(loss);
+            changed = true;
+        }
+        changed
+    }
+
+    /// Whether pitting this lap would undercut the car ahead or lose out to the car behind.
+    /// Returns `None` outside of races.
+    pub fn undercut(&self) -> Option<UndercutEstimate> {
+        if !self.info?.session_type?.is_race() {
+            return None;
+        }
+
+        estimate_undercut(&self.field, self.player_car_index, &self.tyres, self.pit_loss_in_ms())
+    }
+
+    /// Plans the rest of the race from the pace of a lap just finished.
+    /// Returns `None` outside of races or before the tyres are known.
+    pub fn strategy(&self, finished_lap: &Lap) -> Option<Strategy> {
+        if !self.info?.session_type?.is_race() {
+            return None;
+        }
+
+        let total_laps = self.total_laps?;
+        let laps_completed = finished_lap.lap_number + 1;
+        let laps_remaining = total_laps.checked_sub(laps_completed)?;
+
+        let plans = plan_strategy(
+            &self.tyres,
+            laps_completed,
+            total_laps,
+            finished_lap.lap_time,
+            self.pit_loss_in_ms(),
+            self.pit_window,
+        );
+        if plans.is_empty() {
+            return None;
+        }
+
+        let fuel_margin = finished_lap.fuel.as_ref().zip(self.fuel.average_fuel_used())
+            .map(|(fuel, average)| fuel.fuel_in_tank - average * laps_remaining as f32);
+        Some(Strategy { laps_remaining, pit_window: self.pit_window, fuel_margin, plans })
+    }
+
+    /// Starts a new lap for the player, carrying over the setup and any invalidation already known for it.
+    pub fn new_lap(&self, lap_data: LapData) -> Lap {
+        let mut lap = Lap::new(lap_data, self.assists.clone());
+        lap.setup = self.setups.current();
+        lap.invalidations = self.lap_invalidations.iter().filter(|i| i.lap_number == lap.lap_number + 1).copied().collect();
+        lap
+    }
+
+    /// Records the laps deleted by a penalty given to the player.
+    pub fn record_invalidations(&mut self, incident: &Incident) {
+        if incident.vehicle_idx != self.player_car_index {
+            return;
+        }
+
+        for invalidation in LapInvalidation::from_incident(incident) {
+            if let Some(lap) = self.current_lap.as_mut().filter(|lap| lap.lap_number + 1 == invalidation.lap_number) {
+                lap.invalidations.push(invalidation);
+            }
+            self.lap_invalidations.push(invalidation);
+        }
+    }
+
+    /// Computes the delta to the reference lap for the player's current position on track.
+    pub fn live_delta(&self, lap_data: &LapData) -> Option<LiveDelta> {
+        let reference = self.reference_lap.as_ref()?;
+        if lap_data.driver_status != 1 || lap_data.lap_distance < 0.0 {
+            return None;
+        }
+
+        let lap_distance = lap_data.lap_distance;
+        let current_lap_time_in_ms = lap_data.current_lap_time_in_ms;
+        Some(LiveDelta {
+            lap_number: lap_data.current_lap_num,
+            lap_distance,
+            current_lap_time_in_ms,
+            reference_lap_time_in_ms: reference.lap_time_in_ms,
+            delta_in_ms: reference.delta(lap_distance, current_lap_time_in_ms)?,
+        })
+    }
 }
 
 #[derive(Debug, Default, Clone)]
 pub struct Lap {
     pub lap_number: u8,
-    pub lap_time_in_ms: u32,
+    pub lap_time: LapTime,
     pub driver_status: u8,
-    pub sector1_time_in_ms: u16,
-    pub sector2_time_in_ms: u16,
+    pub sector1_time: SectorTime,
+    pub sector2_time: SectorTime,
     pub lap_invalid: bool,
     pub assists: Option<Assists>,
     pub total_distance: f32,
-    pub car_telemetry: BTreeMap<u32, JSONCarTelemetryData>
+    pub car_telemetry: BTreeMap<u32, JSONCarTelemetryData>,
+    pub car_motion: BTreeMap<u32, JSONCarMotionData>,
+    pub motion_ex: BTreeMap<u32, MotionExData>,
+    pub distance_trace: Vec<DistanceSample>,
+    pub fuel: Option<FuelUsage>,
+    pub ers: Option<ErsUsage>,
+    pub conditions: Option<TrackConditions>,
+    /// Incidents the player was involved in on this lap
+    pub incidents: Vec<Incident>,
+    /// Why the lap was deleted, if it was
+    pub invalidations: Vec<LapInvalidation>,
+    /// Damage to the car at the end of the lap
+    pub damage: Option<DamageState>,
+    /// Setup the lap was driven on
+    pub setup: Option<CarSetup>,
 }
 
 impl Lap {
@@ -96,14 +388,84 @@ impl Lap {
             // Specifically, the lap number gets updated between starting to post the lap and
             // actually making the request, but I don't have the time for a proper fix.
             lap_number: lap_data.current_lap_num - 1,
-            lap_time_in_ms: lap_data.current_lap_time_in_ms,
+            lap_time: lap_data.current_lap_time(),
             driver_status: lap_data.driver_status,
-            sector1_time_in_ms: lap_data.sector1_time_in_ms,
-            sector2_time_in_ms: lap_data.sector2_time_in_ms,
+            sector1_time: lap_data.sector1_time(),
+            sector2_time: lap_data.sector2_time(),
             lap_invalid: lap_data.current_lap_invalid,
             assists,
             total_distance: lap_data.total_distance,
-            car_telemetry: BTreeMap::new()
+            car_telemetry: BTreeMap::new(),
+            car_motion: BTreeMap::new(),
+            motion_ex: BTreeMap::new(),
+            distance_trace: Vec::new(),
+            fuel: None,
+            ers: None,
+            conditions: None,
+            incidents: Vec::new(),
+            invalidations: Vec::new(),
+            damage: None,
+            setup: None,
         }
     }
+
+    /// Sector 3 is never sent by the game, so it is derived from the lap time.
+    /// Returns `None` until both of the other sectors have been completed.
+    pub fn sector3_time(&self) -> Option<SectorTime> {
+        self.lap_time.remaining_sector(self.sector1_time, self.sector2_time)
+    }
+
+    /// Time into the lap at which the car passed the given distance.
+    pub fn time_at_distance(&self, lap_distance: f32) -> Option<u32> {
+        delta::time_at_distance(&self.distance_trace, lap_distance)
+    }
+
+    /// Distance around the lap the car had covered at the given lap time.
+    pub fn distance_at_time(&self, current_lap_time_in_ms: u32) -> Option<f32> {
+        delta::distance_at_time(&self.distance_trace, current_lap_time_in_ms)
+    }
+
+    /// Records the fuel burnt so far this lap. Should follow `record_ers`, as updates are
+    /// dropped once the energy counters show the car has crossed the line.
+    pub fn record_fuel(&mut self, car_status: &CarStatusData) {
+        if self.ers.as_ref().is_some_and(ErsUsage::crossed_line) {
+            return;
+        }
+        match &mut self.fuel {
+            Some(fuel) => fuel.record(car_status),
+            None => self.fuel = Some(FuelUsage::new(car_status)),
+        }
+    }
+
+    /// Records the state of the energy store, placing any deployment at the car's latest position.
+    pub fn record_ers(&mut self, car_status: &CarStatusData) {
+        let current_lap_time_in_ms = self.lap_time.as_millis();
+        let lap_distance = self.distance_trace.last().map(|s| s.lap_distance);
+        self.ers.get_or_insert_with(|| ErsUsage::new(car_status)).record(car_status, current_lap_time_in_ms, lap_distance);
+    }
+
+    /// Adds the current track and air temperature to the lap's average conditions.
+    pub fn record_conditions(&mut self, session: &PacketSessionData) {
+        self.conditions.get_or_insert_with(TrackConditions::default).record(session);
+    }
+
+    /// Attaches an incident to the lap if it happened on it.
+    pub fn record_incident(&mut self, incident: &Incident) {
+        if incident.lap_number == Some(self.lap_number + 1) {
+            self.incidents.push(*incident);
+        }
+    }
+
+    /// Records the player's position around the lap. Samples past the new distance are
+    /// dropped first, so a flashback rewinds the trace along with the car.
+    pub fn record_distance(&mut self, lap_data: &LapData) {
+        let lap_distance = lap_data.lap_distance;
+        if lap_data.driver_status != 1 || lap_distance < 0.0 {
+            return;
+        }
+
+        let keep = self.distance_trace.partition_point(|s| s.lap_distance < lap_distance);
+        self.distance_trace.truncate(keep);
+        self.distance_trace.push(DistanceSample { lap_distance, current_lap_time_in_ms: lap_data.current_lap_time_in_ms });
+    }
 }
@@ -0,0 +1,269 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::PacketSessionData;
+
+#[derive(Debug, PartialEq)]
+pub enum SessionInfoError {
+    InvalidSessionType(u8),
+    InvalidFormula(u8),
+    InvalidGameMode(u8),
+    InvalidRuleSet(u8),
+    InvalidSessionLength(u8),
+}
+
+impl fmt::Display for SessionInfoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionInfoError::InvalidSessionType(val) => write!(f, "Invalid session type {}", val),
+            SessionInfoError::InvalidFormula(val) => write!(f, "Invalid formula {}", val),
+            SessionInfoError::InvalidGameMode(val) => write!(f, "Invalid game mode {}", val),
+            SessionInfoError::InvalidRuleSet(val) => write!(f, "Invalid rule set {}", val),
+            SessionInfoError::InvalidSessionLength(val) => write!(f, "Invalid session length {}", val),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionType {
+    Unknown,
+    Practice1,
+    Practice2,
+    Practice3,
+    ShortPractice,
+    Qualifying1,
+    Qualifying2,
+    Qualifying3,
+    ShortQualifying,
+    OneShotQualifying,
+    Race,
+    Race2,
+    Race3,
+    TimeTrial,
+}
+
+impl SessionType {
+    pub fn is_practice(&self) -> bool {
+        matches!(self, SessionType::Practice1 | SessionType::Practice2 | SessionType::Practice3 | SessionType::ShortPractice)
+    }
+
+    pub fn is_qualifying(&self) -> bool {
+        matches!(
+            self,
+            SessionType::Qualifying1
+                | SessionType::Qualifying2
+                | SessionType::Qualifying3
+                | SessionType::ShortQualifying
+                | SessionType::OneShotQualifying
+        )
+    }
+
+    pub fn is_race(&self) -> bool {
+        matches!(self, SessionType::Race | SessionType::Race2 | SessionType::Race3)
+    }
+}
+
+impl TryFrom<u8> for SessionType {
+    type Error = SessionInfoError;
+
+    fn try_from(val: u8) -> Result<Self, Self::Error> {
+        match val {
+            0 => Ok(SessionType::Unknown),
+            1 => Ok(SessionType::Practice1),
+            2 => Ok(SessionType::Practice2),
+            3 => Ok(SessionType::Practice3),
+            4 => Ok(SessionType::ShortPractice),
+            5 => Ok(SessionType::Qualifying1),
+            6 => Ok(SessionType::Qualifying2),
+            7 => Ok(SessionType::Qualifying3),
+            8 => Ok(SessionType::ShortQualifying),
+            9 => Ok(SessionType::OneShotQualifying),
+            10 => Ok(SessionType::Race),
+            11 => Ok(SessionType::Race2),
+            12 => Ok(SessionType::Race3),
+            13 => Ok(SessionType::TimeTrial),
+            _ => Err(SessionInfoError::InvalidSessionType(val)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Formula {
+    F1Modern,
+    F1Classic,
+    F2,
+    F1Generic,
+    Beta,
+    Supercars,
+    Esports,
+    F2_2021,
+}
+
+impl TryFrom<u8> for Formula {
+    type Error = SessionInfoError;
+
+    fn try_from(val: u8) -> Result<Self, Self::Error> {
+        match val {
+            0 => Ok(Formula::F1Modern),
+            1 => Ok(Formula::F1Classic),
+            2 => Ok(Formula::F2),
+            3 => Ok(Formula::F1Generic),
+            4 => Ok(Formula::Beta),
+            5 => Ok(Formula::Supercars),
+            6 => Ok(Formula::Esports),
+            7 => Ok(Formula::F2_2021),
+            _ => Err(SessionInfoError::InvalidFormula(val)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GameMode {
+    EventMode,
+    GrandPrix,
+    GrandPrix23,
+    TimeTrial,
+    Splitscreen,
+    OnlineCustom,
+    OnlineLeague,
+    CareerInvitational,
+    ChampionshipInvitational,
+    Championship,
+    OnlineChampionship,
+    OnlineWeeklyEvent,
+    StoryMode,
+    Career22,
+    Career22Online,
+    Career23,
+    Career23Online,
+    Benchmark,
+}
+
+impl GameMode {
+    pub fn is_career(&self) -> bool {
+        matches!(self, GameMode::Career22 | GameMode::Career22Online | GameMode::Career23 | GameMode::Career23Online)
+    }
+}
+
+impl TryFrom<u8> for GameMode {
+    type Error = SessionInfoError;
+
+    fn try_from(val: u8) -> Result<Self, Self::Error> {
+        match val {
+            0 => Ok(GameMode::EventMode),
+            3 => Ok(GameMode::GrandPrix),
+            4 => Ok(GameMode::GrandPrix23),
+            5 => Ok(GameMode::TimeTrial),
+            6 => Ok(GameMode::Splitscreen),
+            7 => Ok(GameMode::OnlineCustom),
+            8 => Ok(GameMode::OnlineLeague),
+            11 => Ok(GameMode::CareerInvitational),
+            12 => Ok(GameMode::ChampionshipInvitational),
+            13 => Ok(GameMode::Championship),
+            14 => Ok(GameMode::OnlineChampionship),
+            15 => Ok(GameMode::OnlineWeeklyEvent),
+            17 => Ok(GameMode::StoryMode),
+            19 => Ok(GameMode::Career22),
+            20 => Ok(GameMode::Career22Online),
+            21 => Ok(GameMode::Career23),
+            22 => Ok(GameMode::Career23Online),
+            127 => Ok(GameMode::Benchmark),
+            _ => Err(SessionInfoError::InvalidGameMode(val)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RuleSet {
+    PracticeAndQualifying,
+    Race,
+    TimeTrial,
+    TimeAttack,
+    CheckpointChallenge,
+    Autocross,
+    Drift,
+    AverageSpeedZone,
+    RivalDuel,
+}
+
+impl TryFrom<u8> for RuleSet {
+    type Error = SessionInfoError;
+
+    fn try_from(val: u8) -> Result<Self, Self::Error> {
+        match val {
+            0 => Ok(RuleSet::PracticeAndQualifying),
+            1 => Ok(RuleSet::Race),
+            2 => Ok(RuleSet::TimeTrial),
+            4 => Ok(RuleSet::TimeAttack),
+            6 => Ok(RuleSet::CheckpointChallenge),
+            8 => Ok(RuleSet::Autocross),
+            9 => Ok(RuleSet::Drift),
+            10 => Ok(RuleSet::AverageSpeedZone),
+            11 => Ok(RuleSet::RivalDuel),
+            _ => Err(SessionInfoError::InvalidRuleSet(val)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionLength {
+    None,
+    VeryShort,
+    Short,
+    Medium,
+    MediumLong,
+    Long,
+    Full,
+}
+
+impl TryFrom<u8> for SessionLength {
+    type Error = SessionInfoError;
+
+    fn try_from(val: u8) -> Result<Self, Self::Error> {
+        match val {
+            0 => Ok(SessionLength::None),
+            2 => Ok(SessionLength::VeryShort),
+            3 => Ok(SessionLength::Short),
+            4 => Ok(SessionLength::Medium),
+            5 => Ok(SessionLength::MediumLong),
+            6 => Ok(SessionLength::Long),
+            7 => Ok(SessionLength::Full),
+            _ => Err(SessionInfoError::InvalidSessionLength(val)),
+        }
+    }
+}
+
+/// What kind of session is being driven. Values the game sends that aren't known
+/// here are left as `None` rather than failing the whole session.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionInfo {
+    pub session_type: Option<SessionType>,
+    pub formula: Option<Formula>,
+    pub game_mode: Option<GameMode>,
+    pub rule_set: Option<RuleSet>,
+    /// AI difficulty (0-110)
+    pub ai_difficulty: u8,
+    pub session_length: Option<SessionLength>,
+    pub network_game: bool,
+}
+
+impl SessionInfo {
+    pub fn from_session(p: &PacketSessionData) -> Self {
+        Self {
+            session_type: SessionType::try_from(p.session_type).ok(),
+            formula: Formula::try_from(p.formula).ok(),
+            game_mode: GameMode::try_from(p.game_mode).ok(),
+            rule_set: RuleSet::try_from(p.rule_set).ok(),
+            ai_difficulty: p.ai_difficulty,
+            session_length: SessionLength::try_from(p.session_length).ok(),
+            network_game: p.network_game == 1,
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{session::Lap, CarSetupData};
+
+/// A car setup as set in the garage.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CarSetup {
+    pub front_wing: u8,
+    pub rear_wing: u8,
+    pub on_throttle: u8,
+    pub off_throttle: u8,
+    pub front_camber: f32,
+    pub rear_camber: f32,
+    pub front_toe: f32,
+    pub rear_toe: f32,
+    pub front_suspension: u8,
+    pub rear_suspension: u8,
+    pub front_anti_roll_bar: u8,
+    pub rear_anti_roll_bar: u8,
+    pub front_suspension_height: u8,
+    pub rear_suspension_height: u8,
+    pub brake_pressure: u8,
+    pub brake_bias: u8,
+    /// Wheel order is RL, RR, FL, FR
+    pub tyre_pressures: [f32; 4],
+    pub ballast: u8,
+    pub fuel_load: f32,
+}
+
+impl From<&CarSetupData> for CarSetup {
+    fn from(setup: &CarSetupData) -> Self {
+        Self {
+            front_wing: setup.front_wing,
+            rear_wing: setup.rear_wing,
+            on_throttle: setup.on_throttle,
+            off_throttle: setup.off_throttle,
+            front_camber: setup.front_camber,
+            rear_camber: setup.rear_camber,
+            front_toe: setup.front_toe,
+            rear_toe: setup.rear_toe,
+            front_suspension: setup.front_suspension,
+            rear_suspension: setup.rear_suspension,
+            front_anti_roll_bar: setup.front_anti_roll_bar,
+            rear_anti_roll_bar: setup.rear_anti_roll_bar,
+            front_suspension_height: setup.front_suspension_height,
+            rear_suspension_height: setup.rear_suspension_height,
+            brake_pressure: setup.brake_pressure,
+            brake_bias: setup.brake_bias,
+            tyre_pressures: [
+                setup.rear_left_tyre_pressure,
+                setup.rear_right_tyre_pressure,
+                setup.front_left_tyre_pressure,
+                setup.front_right_tyre_pressure,
+            ],
+            ballast: setup.ballast,
+            fuel_load: setup.fuel_load,
+        }
+    }
+}
+
+/// A single setup parameter that differs between two setups.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupChange {
+    pub parameter: String,
+    pub before: f32,
+    pub after: f32,
+}
+
+impl SetupChange {
+    pub fn delta(&self) -> f32 {
+        self.after - self.before
+    }
+}
+
+impl CarSetup {
+    /// Every parameter by name, in the order the garage lists them. The fuel load is left
+    /// out, as the game lowers it as fuel is burnt.
+    pub fn parameters(&self) -> Vec<(&'static str, f32)> {
+        vec![
+            ("frontWing", self.front_wing as f32),
+            ("rearWing", self.rear_wing as f32),
+            ("onThrottle", self.on_throttle as f32),
+            ("offThrottle", self.off_throttle as f32),
+            ("frontCamber", self.front_camber),
+            ("rearCamber", self.rear_camber),
+            ("frontToe", self.front_toe),
+            ("rearToe", self.rear_toe),
+            ("frontSuspension", self.front_suspension as f32),
+            ("rearSuspension", self.rear_suspension as f32),
+            ("frontAntiRollBar", self.front_anti_roll_bar as f32),
+            ("rearAntiRollBar", self.rear_anti_roll_bar as f32),
+            ("frontSuspensionHeight", self.front_suspension_height as f32),
+            ("rearSuspensionHeight", self.rear_suspension_height as f32),
+            ("brakePressure", self.brake_pressure as f32),
+            ("brakeBias", self.brake_bias as f32),
+            ("rearLeftTyrePressure", self.tyre_pressures[0]),
+            ("rearRightTyrePressure", self.tyre_pressures[1]),
+            ("frontLeftTyrePressure", self.tyre_pressures[2]),
+            ("frontRightTyrePressure", self.tyre_pressures[3]),
+            ("ballast", self.ballast as f32),
+        ]
+    }
+
+    /// Parameters changed going from this setup to another.
+    pub fn diff(&self, other: &CarSetup) -> Vec<SetupChange> {
+        self.parameters()
+            .into_iter()
+            .zip(other.parameters())
+            .filter(|((_, before), (_, after))| before != after)
+            .map(|((parameter, before), (_, after))| SetupChange { parameter: parameter.to_string(), before, after })
+            .collect()
+    }
+
+    /// True if the setups only differ in fuel load, which the game lowers as fuel is burnt.
+    pub fn same_setup(&self, other: &CarSetup) -> bool {
+        CarSetup { fuel_load: other.fuel_load, ..*self } == *other
+    }
+}
+
+/// Setup changes between two laps. `None` if either lap has no setup recorded.
+pub fn diff_laps(before: &Lap, after: &Lap) -> Option<Vec<SetupChange>> {
+    Some(before.setup?.diff(&after.setup?))
+}
+
+/// The setup the player started a lap on.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupSnapshot {
+    /// First lap run on this setup (1-based lap number)
+    pub lap_number: u8,
+    pub setup: CarSetup,
+}
+
+/// Every setup the player ran in a session.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupHistory {
+    pub snapshots: Vec<SetupSnapshot>,
+}
+
+impl SetupHistory {
+    /// Takes a snapshot if the setup has changed. Returns true if it had.
+    pub fn record(&mut self, lap_number: u8, setup: CarSetup) -> bool {
+        if self.current().is_some_and(|current| current.same_setup(&setup)) {
+            return false;
+        }
+
+        match self.snapshots.last_mut() {
+            // changed again before the lap was started, e.g. in the garage
+            Some(last) if last.lap_number == lap_number => last.setup = setup,
+            _ => self.snapshots.push(SetupSnapshot { lap_number, setup }),
+        }
+        true
+    }
+
+    pub fn current(&self) -> Option<CarSetup> {
+        self.snapshots.last().map(|snapshot| snapshot.setup)
+    }
+
+    /// Setup in use on the given lap (1-based lap number).
+    pub fn setup_for_lap(&self, lap_number: u8) -> Option<CarSetup> {
+        self.snapshots.iter().rev().find(|snapshot| snapshot.lap_number <= lap_number).map(|snapshot| snapshot.setup)
+    }
+
+    /// Changes between the setup this session ended on and the one another ended on.
+    pub fn diff(&self, other: &SetupHistory) -> Option<Vec<SetupChange>> {
+        Some(self.current()?.diff(&other.current()?))
+    }
+}
@@ -0,0 +1,353 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{lap_time::LapTime, tyres::{TyreModel, DEFAULT_WEAR_THRESHOLD}, PacketSessionData};
+
+/// Time (ms) assumed to be lost to a pit stop until one has been measured
+pub const DEFAULT_PIT_LOSS_IN_MS: u32 = 22_000;
+/// Lap time (ms) lost for each percent of wear on the most worn tyre
+pub(crate) const TIME_PER_WEAR_IN_MS: f32 = 40.0;
+const MAX_STOPS: usize = 3;
+
+/// The game's own pit stop suggestion for the player.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PitWindow {
+    pub ideal_lap: u8,
+    pub latest_lap: u8,
+    pub rejoin_position: u8,
+}
+
+impl PitWindow {
+    pub fn from_session(session: &PacketSessionData) -> Self {
+        Self {
+            ideal_lap: session.pit_stop_window_ideal_lap,
+            latest_lap: session.pit_stop_window_latest_lap,
+            rejoin_position: session.pit_stop_rejoin_position,
+        }
+    }
+
+    pub fn contains(&self, lap: u8) -> bool {
+        self.ideal_lap != 0 && self.ideal_lap <= lap && lap <= self.latest_lap
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedStop {
+    /// Lap the car comes in at the end of
+    pub lap: u8,
+    pub actual_compound: u8,
+    pub visual_compound: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StrategyPlan {
+    pub stops: Vec<PlannedStop>,
+    /// Estimated time to the end of the race (ms)
+    pub estimated_time_in_ms: u32,
+    /// Whether the first stop falls inside the game's pit window
+    pub first_stop_in_window: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Strategy {
+    pub laps_remaining: u8,
+    pub pit_window: Option<PitWindow>,
+    /// Fuel left over at the finish at the current average consumption (kg),
+    /// negative if fuel has to be saved
+    pub fuel_margin: Option<f32>,
+    /// Fastest plan for each number of stops, with the recommended plan first
+    pub plans: Vec<StrategyPlan>,
+}
+
+/// A set of tyres that could run a stint.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TyreOption {
+    actual_compound: u8,
+    visual_compound: u8,
+    wear: f32,
+    wear_per_lap: f32,
+    /// Lap time difference to the fitted set (ms)
+    lap_delta_in_ms: f32,
+}
+
+impl TyreOption {
+    fn is_wet(&self) -> bool {
+        self.visual_compound == 7 || self.visual_compound == 8
+    }
+
+    /// Time (ms) to run `laps` laps on this set, relative to the base lap time on
+    /// the fitted set at `base_wear`. Infinite if the set would wear out.
+    fn stint_time(&self, laps: usize, base_lap_time_in_ms: f32, base_wear: f32) -> f32 {
+        let laps = laps as f32;
+        if self.wear + laps * self.wear_per_lap > DEFAULT_WEAR_THRESHOLD {
+            return f32::INFINITY;
+        }
+
+        let first_lap = base_lap_time_in_ms + self.lap_delta_in_ms + (self.wear - base_wear) * TIME_PER_WEAR_IN_MS;
+        laps * first_lap + TIME_PER_WEAR_IN_MS * self.wear_per_lap * laps * (laps + 1.0) / 2.0
+    }
+}
+
+/// The fitted set first, then the available sets from least to most worn, keeping no more of
+/// each compound than a plan could fit.
+fn tyre_options(tyres: &TyreModel) -> Vec<TyreOption> {
+    let wear_per_lap = |actual_compound: u8, usable_life: u8| {
+        tyres
+            .degradation(actual_compound)
+            .map(|rate| rate.wear_per_lap.iter().copied().fold(0.0, f32::max))
+            .unwrap_or(DEFAULT_WEAR_THRESHOLD / usable_life.max(1) as f32)
+    };
+
+    let fitted = tyres.inventory.sets.iter().find(|set| set.fitted == 1);
+    let Some((actual_compound, visual_compound)) = tyres.compound.or(fitted.map(|set| (set.actual_tyre_compound, set.visual_tyre_compound))) else {
+        return Vec::new();
+    };
+    let wear = tyres
+        .wear
+        .map(|wear| wear.iter().copied().fold(0.0, f32::max))
+        .or(fitted.map(|set| set.wear as f32))
+        .unwrap_or_default();
+    let usable_life = fitted.map_or(0, |set| set.usable_life);
+
+    let mut options = vec![TyreOption {
+        actual_compound,
+        visual_compound,
+        wear,
+        wear_per_lap: wear_per_lap(actual_compound, usable_life),
+        lap_delta_in_ms: 0.0,
+    }];
+    let mut sets: Vec<_> = tyres.inventory.sets.iter().filter(|set| set.available == 1 && set.fitted == 0).collect();
+    sets.sort_by_key(|set| set.wear);
+    for set in sets {
+        // the most worn sets of a compound would never be picked, and leaving them out keeps
+        // the number of sequences to search small
+        if options[1..].iter().filter(|o| o.visual_compound == set.visual_tyre_compound).count() >= MAX_STOPS {
+            continue;
+        }
+        options.push(TyreOption {
+            actual_compound: set.actual_tyre_compound,
+            visual_compound: set.visual_tyre_compound,
+            wear: set.wear as f32,
+            wear_per_lap: wear_per_lap(set.actual_tyre_compound, set.usable_life),
+            lap_delta_in_ms: set.lap_delta_time as f32,
+        });
+    }
+    options
+}
+
+/// Enumerates one-, two- and three-stop plans for the rest of the race and returns the
+/// fastest plan for each number of stops, fastest first.
+pub fn plan_strategy(
+    tyres: &TyreModel,
+    laps_completed: u8,
+    total_laps: u8,
+    base_lap_time: LapTime,
+    pit_loss_in_ms: u32,
+    pit_window: Option<PitWindow>,
+) -> Vec<StrategyPlan> {
+    let options = tyre_options(tyres);
+    let laps_remaining = total_laps.saturating_sub(laps_completed) as usize;
+    if options.is_empty() || base_lap_time.is_zero() {
+        return Vec::new();
+    }
+
+    let mut plans = Vec::new();
+    for stops in 1..=MAX_STOPS.min(laps_remaining.saturating_sub(1)) {
+        let mut best: Option<(f32, Vec<PlannedStop>)> = None;
+        for sequence in sequences(&options, stops) {
+            let stints: Vec<&TyreOption> = sequence.iter().map(|&i| &options[i]).collect();
+            // a dry race has to use at least two different dry compounds
+            let compounds_ok = stints.iter().any(|s| s.is_wet())
+                || stints.iter().any(|s| s.visual_compound != stints[0].visual_compound);
+            if !compounds_ok {
+                continue;
+            }
+
+            let Some((time, stop_laps)) = best_split(&stints, laps_remaining, base_lap_time.as_millis() as f32, options[0].wear, pit_loss_in_ms as f32) else {
+                continue;
+            };
+            if best.as_ref().is_none_or(|(best_time, _)| time < *best_time) {
+                let stops = stop_laps
+                    .iter()
+                    .zip(&stints[1..])
+                    .map(|(&lap, set)| PlannedStop {
+                        lap: laps_completed + lap as u8,
+                        actual_compound: set.actual_compound,
+                        visual_compound: set.visual_compound,
+                    })
+                    .collect();
+                best = Some((time, stops));
+            }
+        }
+
+        if let Some((time, stops)) = best {
+            plans.push(StrategyPlan {
+                first_stop_in_window: pit_window.is_some_and(|window| window.contains(stops[0].lap)),
+                stops,
+                estimated_time_in_ms: time.round() as u32,
+            });
+        }
+    }
+
+    plans.sort_by_key(|plan| plan.estimated_time_in_ms);
+    plans
+}
+
+/// Every ordering of `stops` distinct sets to follow the fitted set (option 0). Sets of the
+/// same compound are interchangeable apart from their wear, so they are taken least worn first.
+fn sequences(options: &[TyreOption], stops: usize) -> Vec<Vec<usize>> {
+    let mut sequences = vec![vec![0]];
+    for _ in 0..stops {
+        let mut longer = Vec::new();
+        for sequence in &sequences {
+            let next = (1..options.len()).filter(|&i| {
+                !sequence.contains(&i)
+                    && (1..i).all(|j| options[j].visual_compound != options[i].visual_compound || sequence.contains(&j))
+            });
+            for i in next {
+                longer.push([sequence.as_slice(), &[i]].concat());
+            }
+        }
+        sequences = longer;
+    }
+    sequences
+}
+
+/// Finds the stop laps that minimise the total time for a sequence of stints.
+/// Returns the total time and the number of laps from now at which each stop is made.
+fn best_split(stints: &[&TyreOption], laps: usize, base_lap_time_in_ms: f32, base_wear: f32, pit_loss_in_ms: f32) -> Option<(f32, Vec<usize>)> {
+    // time[j][l]: fastest way to cover l laps with the first j + 1 stints
+    let mut time = vec![vec![f32::INFINITY; laps + 1]; stints.len()];
+    let mut previous_stop = vec![vec![0; laps + 1]; stints.len()];
+    for (l, first_stint) in time[0].iter_mut().enumerate().skip(1) {
+        *first_stint = stints[0].stint_time(l, base_lap_time_in_ms, base_wear);
+    }
+    for j in 1..stints.len() {
+        for l in j + 1..=laps {
+            for m in j..l {
+                let candidate = time[j - 1][m] + pit_loss_in_ms + stints[j].stint_time(l - m, base_lap_time_in_ms, base_wear);
+                if candidate < time[j][l] {
+                    time[j][l] = candidate;
+                    previous_stop[j][l] = m;
+                }
+            }
+        }
+    }
+
+    let total = time[stints.len() - 1][laps];
+    if !total.is_finite() {
+        return None;
+    }
+
+    let mut stops = Vec::with_capacity(stints.len() - 1);
+    let mut l = laps;
+    for j in (1..stints.len()).rev() {
+        l = previous_stop[j][l];
+        stops.push(l);
+    }
+    stops.reverse();
+    Some((total, stops))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TyreSetData;
+
+    const SOFT: u8 = 16;
+    const MEDIUM: u8 = 17;
+    const HARD: u8 = 18;
+    const PIT_LOSS_IN_MS: u32 = 5_000;
+
+    fn lap_time() -> LapTime {
+        LapTime::from_millis(90_000)
+    }
+
+    fn set(visual_compound: u8, wear: u8, usable_life: u8, fitted: bool) -> TyreSetData {
+        TyreSetData {
+            actual_tyre_compound: visual_compound,
+            visual_tyre_compound: visual_compound,
+            wear,
+            available: 1,
+            recommended_session: 0,
+            life_span: usable_life,
+            usable_life,
+            lap_delta_time: 0,
+            fitted: fitted as u8,
+        }
+    }
+
+    /// New softs fitted, with the given sets in the inventory.
+    fn tyres(usable_life: u8, others: &[(u8, u8)]) -> TyreModel {
+        let mut tyres = TyreModel { compound: Some((SOFT, SOFT)), wear: Some([0.0; 4]), ..Default::default() };
+        tyres.inventory.sets.push(set(SOFT, 0, usable_life, true));
+        tyres.inventory.sets.extend(others.iter().map(|&(compound, wear)| set(compound, wear, usable_life, false)));
+        tyres
+    }
+
+    fn compounds(plan: &StrategyPlan) -> Vec<u8> {
+        plan.stops.iter().map(|stop| stop.visual_compound).collect()
+    }
+
+    #[test]
+    fn plans_have_to_use_two_dry_compounds() {
+        let only_softs = tyres(40, &[(SOFT, 0), (SOFT, 0), (SOFT, 0)]);
+        assert!(plan_strategy(&only_softs, 0, 30, lap_time(), PIT_LOSS_IN_MS, None).is_empty());
+
+        let plans = plan_strategy(&tyres(40, &[(SOFT, 0), (SOFT, 0), (MEDIUM, 0)]), 0, 30, lap_time(), PIT_LOSS_IN_MS, None);
+        assert!(!plans.is_empty());
+        assert!(plans.iter().all(|plan| compounds(plan).contains(&MEDIUM)), "{:?}", plans);
+    }
+
+    #[test]
+    fn compounds_can_be_repeated_on_their_next_best_set() {
+        let plans = plan_strategy(&tyres(16, &[(HARD, 0), (HARD, 10), (MEDIUM, 40)]), 0, 30, lap_time(), PIT_LOSS_IN_MS, None);
+
+        let two_stop = plans.iter().find(|plan| plan.stops.len() == 2).unwrap();
+        assert_eq!(compounds(two_stop), vec![HARD, HARD]);
+    }
+
+    #[test]
+    fn stops_fall_within_the_race_and_the_pit_window() {
+        let tyres = tyres(40, &[(MEDIUM, 0)]);
+        let in_window = PitWindow { ideal_lap: 22, latest_lap: 28, rejoin_position: 0 };
+        let plans = plan_strategy(&tyres, 10, 40, lap_time(), PIT_LOSS_IN_MS, Some(in_window));
+
+        let one_stop = &plans[0];
+        assert_eq!(one_stop.stops.len(), 1);
+        assert_eq!(one_stop.stops[0].lap, 25);
+        assert!(one_stop.first_stop_in_window);
+
+        let late_window = PitWindow { ideal_lap: 30, latest_lap: 35, rejoin_position: 0 };
+        let plans = plan_strategy(&tyres, 10, 40, lap_time(), PIT_LOSS_IN_MS, Some(late_window));
+        assert!(!plans[0].first_stop_in_window);
+        assert!(plans.iter().flat_map(|plan| &plan.stops).all(|stop| 10 < stop.lap && stop.lap < 40));
+    }
+
+    #[test]
+    fn stops_more_often_when_degradation_is_high() {
+        // two new sets, so no more than two stops
+        let others = [(MEDIUM, 0), (MEDIUM, 0)];
+
+        let low = plan_strategy(&tyres(60, &others), 0, 30, lap_time(), PIT_LOSS_IN_MS, None);
+        assert_eq!(low[0].stops.len(), 1);
+
+        let high = plan_strategy(&tyres(16, &others), 0, 30, lap_time(), PIT_LOSS_IN_MS, None);
+        assert_eq!(high[0].stops.len(), 2);
+        assert!(high.iter().any(|plan| plan.stops.len() == 1 && plan.estimated_time_in_ms > high[0].estimated_time_in_ms));
+    }
+
+    #[test]
+    fn splits_evenly_between_identical_sets() {
+        let option = TyreOption { actual_compound: SOFT, visual_compound: SOFT, wear: 0.0, wear_per_lap: 2.0, lap_delta_in_ms: 0.0 };
+        let (time, stops) = best_split(&[&option, &option], 20, 90_000.0, 0.0, 5_000.0).unwrap();
+
+        assert_eq!(stops, vec![10]);
+        assert_eq!(time, 2.0 * option.stint_time(10, 90_000.0, 0.0) + 5_000.0);
+        // a single stint can't last the distance
+        assert_eq!(best_split(&[&option], 40, 90_000.0, 0.0, 5_000.0), None);
+    }
+}
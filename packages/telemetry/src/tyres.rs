@@ -0,0 +1,412 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{CarDamageData, CarStatusData, PacketSessionHistoryData, PacketTyreSetData, TyreSetData};
+
+/// Wear (%) on the worst tyre at which a set is considered finished
+pub const DEFAULT_WEAR_THRESHOLD: f32 = 70.0;
+
+/// Tyre wear at the end of a lap. Wheel order is RL, RR, FL, FR.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TyreLap {
+    pub lap_number: u8,
+    pub tyres_age_laps: u8,
+    pub wear: [f32; 4],
+}
+
+impl TyreLap {
+    /// Wear on the most worn tyre.
+    pub fn max_wear(&self) -> f32 {
+        self.wear.iter().copied().fold(0.0, f32::max)
+    }
+}
+
+/// A run on a single set of tyres.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TyreStint {
+    pub actual_compound: u8,
+    pub visual_compound: u8,
+    pub start_lap: u8,
+    /// Index of the set in the player's inventory, once the tyre sets packet has said which is fitted
+    pub set_index: Option<u8>,
+    /// Last lap on this set, `None` while the set is still fitted
+    pub end_lap: Option<u8>,
+    pub laps: Vec<TyreLap>,
+}
+
+/// Linear fit of wear against tyre age for one compound.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DegradationRate {
+    pub actual_compound: u8,
+    /// Wear gained per lap on each tyre (%)
+    pub wear_per_lap: [f32; 4],
+    /// Number of laps the fit was made from
+    pub laps: u32,
+}
+
+/// Follows the player's tyres through a session, splitting laps into stints
+/// whenever the set is changed.
+#[derive(Debug, Default, Clone)]
+pub struct TyreModel {
+    pub stints: Vec<TyreStint>,
+    /// Latest wear reported by the car damage packet
+    pub wear: Option<[f32; 4]>,
+    /// Latest (actual, visual) compound reported by the car status packet
+    pub compound: Option<(u8, u8)>,
+    pub tyres_age_laps: u8,
+    /// Tyre sets allocated to the player, including the fitted one
+    pub inventory: TyreInventory,
+}
+
+/// The player's tyre sets for a race weekend.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TyreInventory {
+    pub weekend_link_identifier: u32,
+    pub sets: Vec<TyreSetData>,
+    pub fitted_index: Option<u8>,
+    /// Laps run on each set over the weekend, by set index
+    pub laps_run: Vec<u8>,
+}
+
+impl TyreInventory {
+    pub fn new(weekend_link_identifier: u32) -> Self {
+        Self { weekend_link_identifier, ..Default::default() }
+    }
+
+    /// Replaces the sets with the game's latest view of them.
+    /// Returns true if a different set has been fitted.
+    pub fn update(&mut self, tyre_sets: &PacketTyreSetData) -> bool {
+        self.sets = tyre_sets.tyre_set_data.to_vec();
+        self.laps_run.resize(self.sets.len(), 0);
+
+        let fitted_index = Some(tyre_sets.fitted_idx).filter(|&i| (i as usize) < self.sets.len());
+        let changed = fitted_index != self.fitted_index;
+        self.fitted_index = fitted_index;
+        changed
+    }
+
+    pub fn fitted(&self) -> Option<&TyreSetData> {
+        self.sets.get(self.fitted_index? as usize)
+    }
+
+    /// Index of the fitted set, if it is of the given compound.
+    fn fitted_index_of(&self, actual_compound: u8, visual_compound: u8) -> Option<u8> {
+        let fitted = self.fitted()?;
+        (fitted.actual_tyre_compound == actual_compound && fitted.visual_tyre_compound == visual_compound)
+            .then_some(self.fitted_index?)
+    }
+
+    /// Sets that are still available, with their index in the inventory.
+    pub fn available(&self) -> impl Iterator<Item = (usize, &TyreSetData)> {
+        self.sets.iter().enumerate().filter(|(_, set)| set.available == 1)
+    }
+}
+
+impl TyreModel {
+    pub fn update_status(&mut self, car_status: &CarStatusData) {
+        self.compound = Some((car_status.actual_tyre_compound, car_status.visual_tyre_compound));
+        self.tyres_age_laps = car_status.tyres_age_laps;
+    }
+
+    pub fn update_damage(&mut self, car_damage: &CarDamageData) {
+        self.wear = Some(car_damage.tyres_wear);
+    }
+
+    /// Links a newly fitted set to the open stint, if it doesn't have one yet.
+    /// Returns true if a different set has been fitted.
+    pub fn update_sets(&mut self, tyre_sets: &PacketTyreSetData) -> bool {
+        let changed = self.inventory.update(tyre_sets);
+        if !changed {
+            return false;
+        }
+
+        if let Some(stint) = self.stints.last_mut().filter(|stint| stint.end_lap.is_none() && stint.set_index.is_none()) {
+            stint.set_index = self.inventory.fitted_index_of(stint.actual_compound, stint.visual_compound);
+            // laps sampled before the set was known
+            if let Some(laps_run) = stint.set_index.and_then(|i| self.inventory.laps_run.get_mut(i as usize)) {
+                *laps_run = laps_run.saturating_add(stint.laps.len() as u8);
+            }
+        }
+        true
+    }
+
+    /// Samples the tyres at the end of a lap (1-based lap number).
+    /// A change of compound or a drop in tyre age closes the current stint. The lap the
+    /// tyres were changed on is not sampled, as its wear belongs to neither set.
+    /// A new stint is only linked to a set once the tyre sets packet shows a different set
+    /// fitted, as it only comes round about once a second and may still show the old one.
+    pub fn finish_lap(&mut self, lap_number: u8) -> Option<TyreLap> {
+        let (actual_compound, visual_compound) = self.compound?;
+        let wear = self.wear?;
+        let tyre_lap = TyreLap { lap_number, tyres_age_laps: self.tyres_age_laps, wear };
+
+        let sampled_set = match self.stints.last_mut() {
+            Some(stint) if stint.end_lap.is_none() => {
+                let last_age = stint.laps.last().map_or(0, |l| l.tyres_age_laps);
+                if stint.actual_compound == actual_compound
+                    && stint.visual_compound == visual_compound
+                    && tyre_lap.tyres_age_laps >= last_age
+                {
+                    stint.laps.push(tyre_lap);
+                    stint.set_index
+                } else {
+                    stint.end_lap = Some(lap_number);
+                    // the tyre sets packet may already have caught up with the stop
+                    let previous_set_index = stint.set_index;
+                    let set_index = self
+                        .inventory
+                        .fitted_index_of(actual_compound, visual_compound)
+                        .filter(|&i| Some(i) != previous_set_index);
+                    self.stints.push(TyreStint {
+                        actual_compound,
+                        visual_compound,
+                        start_lap: lap_number + 1,
+                        set_index,
+                        end_lap: None,
+                        laps: Vec::new(),
+                    });
+                    return None;
+                }
+            }
+            _ => {
+                let set_index = self.inventory.fitted_index_of(actual_compound, visual_compound);
+                self.stints.push(TyreStint {
+                    actual_compound,
+                    visual_compound,
+                    start_lap: lap_number,
+                    set_index,
+                    end_lap: None,
+                    laps: vec![tyre_lap],
+                });
+                set_index
+            }
+        };
+
+        if let Some(laps_run) = sampled_set.and_then(|i| self.inventory.laps_run.get_mut(i as usize)) {
+            *laps_run += 1;
+        }
+        Some(tyre_lap)
+    }
+
+    /// Tyre sample taken at the end of the given lap (1-based lap number).
+    pub fn lap(&self, lap_number: u8) -> Option<&TyreLap> {
+        self.stints.iter().flat_map(|s| &s.laps).find(|l| l.lap_number == lap_number)
+    }
+
+    /// Takes the stint boundaries from the game's session history, which also covers
+    /// stops made before the session was joined.
+    pub fn apply_history(&mut self, history: &PacketSessionHistoryData) {
+        let mut start_lap = 1;
+        let game_stints = history.tyre_stints_history_data;
+        for game_stint in game_stints.iter().take(history.num_tyre_stints as usize) {
+            let end_lap = game_stint.end_lap;
+            if end_lap == 255 {
+                // only the latest stint is still fitted, any before it ended where the next began
+                let mut next_start_lap: Option<u8> = None;
+                for stint in self.stints.iter_mut().rev().filter(|s| s.start_lap >= start_lap) {
+                    stint.end_lap = next_start_lap.map(|lap| lap.saturating_sub(1));
+                    next_start_lap = Some(stint.start_lap);
+                }
+                break;
+            }
+
+            for stint in self.stints.iter_mut().filter(|s| s.start_lap >= start_lap && s.start_lap <= end_lap) {
+                stint.end_lap = Some(end_lap);
+            }
+            start_lap = end_lap.saturating_add(1);
+        }
+    }
+
+    /// Fits a wear rate per tyre for a compound from every lap run on it this session.
+    /// Returns `None` until laps at two different tyre ages have been sampled.
+    pub fn degradation(&self, actual_compound: u8) -> Option<DegradationRate> {
+        let laps: Vec<&TyreLap> = self
+            .stints
+            .iter()
+            .filter(|s| s.actual_compound == actual_compound)
+            .flat_map(|s| &s.laps)
+            .collect();
+        if laps.len() < 2 {
+            return None;
+        }
+
+        let n = laps.len() as f32;
+        let mean_age = laps.iter().map(|l| l.tyres_age_laps as f32).sum::<f32>() / n;
+        let age_variance: f32 = laps.iter().map(|l| (l.tyres_age_laps as f32 - mean_age).powi(2)).sum();
+        if age_variance == 0.0 {
+            return None;
+        }
+
+        let mut wear_per_lap = [0.0; 4];
+        for (wheel, rate) in wear_per_lap.iter_mut().enumerate() {
+            let mean_wear = laps.iter().map(|l| l.wear[wheel]).sum::<f32>() / n;
+            let covariance: f32 = laps
+                .iter()
+                .map(|l| (l.tyres_age_laps as f32 - mean_age) * (l.wear[wheel] - mean_wear))
+                .sum();
+            *rate = covariance / age_variance;
+        }
+
+        Some(DegradationRate { actual_compound, wear_per_lap, laps: laps.len() as u32 })
+    }
+
+    /// Degradation rates for every compound used this session.
+    pub fn degradation_rates(&self) -> Vec<DegradationRate> {
+        let mut compounds: Vec<u8> = self.stints.iter().map(|s| s.actual_compound).collect();
+        compounds.sort_unstable();
+        compounds.dedup();
+        compounds.into_iter().filter_map(|c| self.degradation(c)).collect()
+    }
+
+    /// Laps until the first tyre of the current set reaches the wear threshold,
+    /// extrapolated from the current compound's degradation rate.
+    pub fn laps_remaining(&self, wear_threshold: f32) -> Option<f32> {
+        let (actual_compound, _) = self.compound?;
+        let wear = self.wear?;
+        let rate = self.degradation(actual_compound)?;
+
+        (0..4)
+            .filter(|&wheel| rate.wear_per_lap[wheel] > 0.0)
+            .map(|wheel| ((wear_threshold - wear[wheel]) / rate.wear_per_lap[wheel]).max(0.0))
+            .min_by(f32::total_cmp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOFT: (u8, u8) = (18, 16);
+    const MEDIUM: (u8, u8) = (17, 17);
+
+    /// Runs laps on one set, wearing every tyre by `wear_per_lap` plus a fixed offset per wheel.
+    fn run_laps(model: &mut TyreModel, compound: (u8, u8), laps: std::ops::RangeInclusive<u8>, first_age: u8, wear_per_lap: f32) {
+        for (age, lap_number) in (first_age..).zip(laps) {
+            model.compound = Some(compound);
+            model.tyres_age_laps = age;
+            model.wear = Some([0.0, 0.5, 1.0, 1.5].map(|offset| offset + age as f32 * wear_per_lap));
+            model.finish_lap(lap_number);
+        }
+    }
+
+    fn history(end_laps: &[u8]) -> PacketSessionHistoryData {
+        // SAFETY: the packet is plain integers, for which all zeroes is valid
+        let mut history: PacketSessionHistoryData = unsafe { std::mem::zeroed() };
+        history.num_tyre_stints = end_laps.len() as u8;
+        for (stint, &end_lap) in history.tyre_stints_history_data.iter_mut().zip(end_laps) {
+            stint.end_lap = end_lap;
+        }
+        history
+    }
+
+    #[test]
+    fn splits_stints_on_a_compound_change() {
+        let mut model = TyreModel::default();
+        run_laps(&mut model, SOFT, 1..=4, 1, 3.0);
+
+        // pitted on lap 5 for mediums, so its wear belongs to neither set
+        model.compound = Some(MEDIUM);
+        model.tyres_age_laps = 0;
+        assert_eq!(model.finish_lap(5), None);
+        run_laps(&mut model, MEDIUM, 6..=7, 1, 2.0);
+
+        assert_eq!(model.stints.len(), 2);
+        assert_eq!((model.stints[0].start_lap, model.stints[0].end_lap), (1, Some(5)));
+        assert_eq!(model.stints[0].laps.len(), 4);
+        assert_eq!((model.stints[1].start_lap, model.stints[1].end_lap), (6, None));
+        assert_eq!(model.stints[1].visual_compound, MEDIUM.1);
+        assert_eq!(model.lap(6).map(|l| l.tyres_age_laps), Some(1));
+        assert_eq!(model.lap(5), None);
+    }
+
+    #[test]
+    fn splits_stints_when_a_new_set_of_the_same_compound_is_fitted() {
+        let mut model = TyreModel::default();
+        run_laps(&mut model, SOFT, 1..=5, 1, 3.0);
+        run_laps(&mut model, SOFT, 6..=8, 0, 3.0);
+
+        assert_eq!(model.stints.len(), 2);
+        assert_eq!(model.stints[0].end_lap, Some(6));
+        assert_eq!(model.stints[1].start_lap, 7);
+        assert_eq!(model.stints[1].laps.len(), 2);
+    }
+
+    #[test]
+    fn takes_stint_ends_from_the_session_history() {
+        let mut model = TyreModel::default();
+        // joined on lap 3, after the game's first stint had started
+        run_laps(&mut model, SOFT, 3..=4, 3, 3.0);
+        run_laps(&mut model, MEDIUM, 5..=9, 0, 2.0);
+        run_laps(&mut model, SOFT, 10..=12, 0, 3.0);
+        for stint in &mut model.stints {
+            stint.end_lap = None;
+        }
+
+        model.apply_history(&history(&[4, 9, 255]));
+
+        let ends: Vec<Option<u8>> = model.stints.iter().map(|s| s.end_lap).collect();
+        assert_eq!(ends, vec![Some(4), Some(9), None]);
+    }
+
+    #[test]
+    fn still_fitted_stints_end_where_the_next_one_begins() {
+        let mut model = TyreModel::default();
+        run_laps(&mut model, SOFT, 1..=3, 1, 3.0);
+        run_laps(&mut model, MEDIUM, 4..=6, 0, 2.0);
+        for stint in &mut model.stints {
+            stint.end_lap = None;
+        }
+
+        // the history packet hasn't caught up with the stop yet
+        model.apply_history(&history(&[255]));
+
+        assert_eq!(model.stints[0].end_lap, Some(model.stints[1].start_lap - 1));
+        assert_eq!(model.stints[1].end_lap, None);
+    }
+
+    #[test]
+    fn fits_wear_per_lap_for_each_tyre() {
+        let mut model = TyreModel::default();
+        run_laps(&mut model, SOFT, 1..=1, 1, 3.0);
+        assert_eq!(model.degradation(SOFT.0), None);
+
+        run_laps(&mut model, SOFT, 2..=6, 2, 3.0);
+        run_laps(&mut model, MEDIUM, 7..=12, 0, 2.0);
+
+        let soft = model.degradation(SOFT.0).unwrap();
+        assert_eq!(soft.laps, 6);
+        assert!(soft.wear_per_lap.iter().all(|rate| (rate - 3.0).abs() < 1e-4), "{:?}", soft);
+        let medium = model.degradation(MEDIUM.0).unwrap();
+        assert!(medium.wear_per_lap.iter().all(|rate| (rate - 2.0).abs() < 1e-4), "{:?}", medium);
+        assert_eq!(model.degradation_rates().len(), 2);
+    }
+
+    #[test]
+    fn needs_two_tyre_ages_to_fit() {
+        let mut model = TyreModel::default();
+        model.stints.push(TyreStint {
+            actual_compound: SOFT.0,
+            visual_compound: SOFT.1,
+            start_lap: 1,
+            set_index: None,
+            end_lap: None,
+            laps: vec![TyreLap { lap_number: 1, tyres_age_laps: 2, wear: [5.0; 4] }; 2],
+        });
+
+        assert_eq!(model.degradation(SOFT.0), None);
+    }
+
+    #[test]
+    fn extrapolates_laps_until_the_most_worn_tyre_hits_the_threshold() {
+        let mut model = TyreModel::default();
+        run_laps(&mut model, MEDIUM, 1..=10, 1, 2.0);
+
+        // worst tyre is at 21.5% and wears 2% a lap
+        let laps = model.laps_remaining(DEFAULT_WEAR_THRESHOLD).unwrap();
+        assert!((laps - 24.25).abs() < 1e-3, "{}", laps);
+        assert_eq!(model.laps_remaining(10.0), Some(0.0));
+    }
+}
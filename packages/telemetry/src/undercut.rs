@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{pit_stops::CarTyres, strategy::TIME_PER_WEAR_IN_MS, tyres::TyreModel, LapData, PacketCarStatusData, PacketLapData};
+
+/// Time (ms) a set of cold tyres costs on the out lap
+const OUT_LAP_WARM_UP_IN_MS: i32 = 1_000;
+
+/// Latest lap data and tyres of every car in the session.
+#[derive(Debug, Default, Clone)]
+pub struct Field {
+    pub lap_data: Vec<LapData>,
+    pub tyres: Vec<CarTyres>,
+}
+
+impl Field {
+    pub fn update_laps(&mut self, lap_packet: &PacketLapData) {
+        self.lap_data = lap_packet.lap_data.to_vec();
+    }
+
+    pub fn update_status(&mut self, car_status_packet: &PacketCarStatusData) {
+        self.tyres = car_status_packet
+            .car_status_data
+            .iter()
+            .map(|status| CarTyres {
+                actual_compound: status.actual_tyre_compound,
+                visual_compound: status.visual_tyre_compound,
+                tyres_age_laps: status.tyres_age_laps,
+            })
+            .collect();
+    }
+
+    /// Index of the active car in the given race position.
+    fn car_in_position(&self, position: u8) -> Option<usize> {
+        self.lap_data.iter().position(|lap| lap.car_position == position && lap.result_status == 2)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RivalEstimate {
+    pub car_index: u8,
+    pub position: u8,
+    /// Gap between the player and this car (ms)
+    pub gap_in_ms: u32,
+    pub tyres_age_laps: u8,
+    /// Time the player's fresh tyres would gain on this car's over the out lap (ms)
+    pub tyre_gain_in_ms: i32,
+    /// Projected gap once the stops have played out (ms), positive if the player comes out ahead
+    pub margin_in_ms: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndercutEstimate {
+    pub lap_number: u8,
+    pub pit_loss_in_ms: u32,
+    /// The car ahead, assuming it pits on the following lap. `None` while it is in the pits
+    pub car_ahead: Option<RivalEstimate>,
+    /// The car behind, assuming it stays out. `None` while it is in the pits
+    pub car_behind: Option<RivalEstimate>,
+    /// Pitting this lap should get the player ahead of the car in front
+    pub undercut: bool,
+    /// Pitting this lap should drop the player behind the car behind
+    pub lose_to_car_behind: bool,
+}
+
+/// Estimates what pitting on the current lap would do against the cars either side of the player.
+pub fn estimate_undercut(field: &Field, player_car_index: u8, tyres: &TyreModel, pit_loss_in_ms: u32) -> Option<UndercutEstimate> {
+    let player = field.lap_data.get(player_car_index as usize)?;
+
+    // lap time lost per lap of tyre age, from the player's own degradation
+    let (actual_compound, _) = tyres.compound?;
+    let fallback_rate = tyres.degradation(actual_compound)?;
+    let time_per_lap_of_age = |compound: u8| {
+        let rate = tyres.degradation(compound).unwrap_or(fallback_rate);
+        rate.wear_per_lap.iter().copied().fold(0.0, f32::max) * TIME_PER_WEAR_IN_MS
+    };
+
+    let rival = |car_index: usize, gap_in_ms: u16| {
+        let lap_data = field.lap_data[car_index];
+        let car_tyres = field.tyres.get(car_index).copied().unwrap_or_default();
+        let tyres_age_laps = car_tyres.tyres_age_laps;
+        let tyre_gain_in_ms = (tyres_age_laps as f32 * time_per_lap_of_age(car_tyres.actual_compound)).round() as i32 - OUT_LAP_WARM_UP_IN_MS;
+        RivalEstimate {
+            car_index: car_index as u8,
+            position: lap_data.car_position,
+            gap_in_ms: gap_in_ms as u32,
+            tyres_age_laps,
+            tyre_gain_in_ms,
+            margin_in_ms: 0,
+        }
+    };
+
+    // a car already in the pits has made its stop, so there is nothing to undercut or defend
+    let on_track = |&i: &usize| field.lap_data[i].pit_status == 0;
+
+    let car_ahead = player.car_position.checked_sub(1).and_then(|p| field.car_in_position(p)).filter(on_track).map(|i| {
+        let mut estimate = rival(i, player.delta_to_car_in_front_in_ms);
+        // both cars lose the same time in the pits, so only the out lap matters
+        estimate.margin_in_ms = estimate.tyre_gain_in_ms - estimate.gap_in_ms as i32;
+        estimate
+    });
+    let car_behind = field.car_in_position(player.car_position + 1).filter(on_track).map(|i| {
+        let mut estimate = rival(i, field.lap_data[i].delta_to_car_in_front_in_ms);
+        estimate.margin_in_ms = estimate.gap_in_ms as i32 + estimate.tyre_gain_in_ms - pit_loss_in_ms as i32;
+        estimate
+    });
+
+    Some(UndercutEstimate {
+        lap_number: player.current_lap_num,
+        pit_loss_in_ms,
+        undercut: car_ahead.is_some_and(|car| car.margin_in_ms > 0),
+        lose_to_car_behind: car_behind.is_some_and(|car| car.margin_in_ms < 0),
+        car_ahead,
+        car_behind,
+    })
+}
@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+
+use crate::PacketSessionData;
+
+/// How often the weather is sampled and the forecast recorded (s)
+const SAMPLE_INTERVAL: f32 = 60.0;
+/// How far ahead (minutes) the forecast is checked for a change of tyres
+const ALERT_LOOKAHEAD: u8 = 10;
+/// Rain chance (%) at or above which intermediates are called for
+const INTERMEDIATE_RAIN_PERCENTAGE: u8 = 40;
+/// Rain chance (%) at or above which full wets are called for
+const WET_RAIN_PERCENTAGE: u8 = 75;
+/// Rain chance (%) below which it is safe to go back to slicks
+const SLICK_RAIN_PERCENTAGE: u8 = 20;
+
+/// The weather observed at a point in the session.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeatherSample {
+    /// Time since the session started (s)
+    pub session_time: f32,
+    pub weather: u8,
+    /// Chance of rain right now, from the forecast for the current minute
+    pub rain_percentage: Option<u8>,
+    /// Track temperature (°C)
+    pub track_temperature: i8,
+    /// Air temperature (°C)
+    pub air_temperature: i8,
+}
+
+/// Average track conditions over a lap.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackConditions {
+    /// Mean track temperature (°C)
+    pub track_temperature: f32,
+    /// Mean air temperature (°C)
+    pub air_temperature: f32,
+    /// Weather when the lap finished
+    pub weather: u8,
+    pub samples: u32,
+}
+
+impl TrackConditions {
+    pub fn record(&mut self, session: &PacketSessionData) {
+        self.samples += 1;
+        let n = self.samples as f32;
+        self.track_temperature += (session.track_temperature as f32 - self.track_temperature) / n;
+        self.air_temperature += (session.air_temperature as f32 - self.air_temperature) / n;
+        self.weather = session.weather;
+    }
+}
+
+/// What the forecast said the weather would be at a given time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Prediction {
+    target_session_time: f32,
+    time_offset: u8,
+    weather: u8,
+    rain_percentage: u8,
+}
+
+/// How well the forecast predicted the weather a given number of minutes ahead.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForecastAccuracy {
+    pub time_offset: u8,
+    pub samples: u32,
+    /// Predictions that got the weather type right
+    pub weather_correct: u32,
+    /// Mean difference between the predicted and observed chance of rain (%)
+    pub mean_rain_percentage_error: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TyreCall {
+    Slicks,
+    Intermediates,
+    Wets,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeatherAlert {
+    pub call: TyreCall,
+    /// Highest chance of rain in the lookahead window (%)
+    pub rain_percentage: u8,
+    /// Minutes until that chance of rain is forecast
+    pub time_offset: u8,
+}
+
+/// Weather observed over a session, and how it compared with the forecast.
+#[derive(Debug, Default, Clone)]
+pub struct WeatherTimeline {
+    pub samples: Vec<WeatherSample>,
+    pub accuracy: Vec<ForecastAccuracy>,
+    /// Tyres the forecast currently calls for
+    pub call: Option<TyreCall>,
+    predictions: Vec<Prediction>,
+}
+
+impl WeatherTimeline {
+    /// Samples the weather once a minute and checks the forecast for a change of tyres.
+    /// Returns an alert whenever the tyres called for change.
+    pub fn update(&mut self, session: &PacketSessionData) -> Option<WeatherAlert> {
+        let session_time = session.header.session_time;
+        let session_type = session.session_type;
+        let forecast: Vec<_> = session
+            .weather_forecast_samples
+            .iter()
+            .take(session.num_weather_forecast_samples as usize)
+            .filter(|sample| sample.session_type == session_type)
+            .copied()
+            .collect();
+
+        let due = self.samples.last().is_none_or(|last| session_time - last.session_time >= SAMPLE_INTERVAL);
+        if due {
+            let observed = WeatherSample {
+                session_time,
+                weather: session.weather,
+                rain_percentage: forecast.iter().find(|sample| sample.time_offset == 0).map(|sample| sample.rain_percentage),
+                track_temperature: session.track_temperature,
+                air_temperature: session.air_temperature,
+            };
+            self.evaluate(&observed);
+            self.samples.push(observed);
+
+            self.predictions.extend(forecast.iter().filter(|sample| sample.time_offset > 0).map(|sample| Prediction {
+                target_session_time: session_time + sample.time_offset as f32 * 60.0,
+                time_offset: sample.time_offset,
+                weather: sample.weather,
+                rain_percentage: sample.rain_percentage,
+            }));
+        }
+
+        let (time_offset, rain_percentage) = forecast
+            .iter()
+            .filter(|sample| sample.time_offset <= ALERT_LOOKAHEAD)
+            .map(|sample| (sample.time_offset, sample.rain_percentage))
+            .max_by_key(|&(_, rain_percentage)| rain_percentage)?;
+
+        let call = match self.call {
+            _ if rain_percentage >= WET_RAIN_PERCENTAGE => TyreCall::Wets,
+            _ if rain_percentage >= INTERMEDIATE_RAIN_PERCENTAGE => TyreCall::Intermediates,
+            // stay on wet weather tyres until the rain has properly cleared
+            Some(call) if call != TyreCall::Slicks && rain_percentage >= SLICK_RAIN_PERCENTAGE => TyreCall::Intermediates,
+            _ => TyreCall::Slicks,
+        };
+        if self.call == Some(call) || (self.call.is_none() && call == TyreCall::Slicks) {
+            self.call = Some(call);
+            return None;
+        }
+
+        self.call = Some(call);
+        Some(WeatherAlert { call, rain_percentage, time_offset })
+    }
+
+    /// Scores every prediction made for the time of an observation.
+    fn evaluate(&mut self, observed: &WeatherSample) {
+        let window = SAMPLE_INTERVAL / 2.0;
+        let (due, pending): (Vec<Prediction>, Vec<Prediction>) = self
+            .predictions
+            .iter()
+            .partition(|p| p.target_session_time <= observed.session_time + window);
+        self.predictions = pending;
+
+        for prediction in due.iter().filter(|p| (p.target_session_time - observed.session_time).abs() <= window) {
+            let accuracy = match self.accuracy.iter_mut().find(|a| a.time_offset == prediction.time_offset) {
+                Some(accuracy) => accuracy,
+                None => {
+                    self.accuracy.push(ForecastAccuracy { time_offset: prediction.time_offset, ..Default::default() });
+                    self.accuracy.last_mut().unwrap()
+                }
+            };
+
+            accuracy.samples += 1;
+            if prediction.weather == observed.weather {
+                accuracy.weather_correct += 1;
+            }
+            if let Some(rain_percentage) = observed.rain_percentage {
+                let error = (prediction.rain_percentage as f32 - rain_percentage as f32).abs();
+                accuracy.mean_rain_percentage_error += (error - accuracy.mean_rain_percentage_error) / accuracy.samples as f32;
+            }
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{session_info::SessionType, CarDamageData, LapData, PacketSessionData};
+
+/// Identifiers the game uses to tie sessions together. They persist across saves.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeekendLink {
+    pub season_link_identifier: u32,
+    pub weekend_link_identifier: u32,
+    pub session_link_identifier: u32,
+}
+
+impl WeekendLink {
+    pub fn from_session(p: &PacketSessionData) -> Self {
+        Self {
+            season_link_identifier: p.season_link_identifier,
+            weekend_link_identifier: p.weekend_link_identifier,
+            session_link_identifier: p.session_link_identifier,
+        }
+    }
+}
+
+/// Wear on each power unit component (%).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineWear {
+    pub ice: u8,
+    pub mgu_h: u8,
+    pub mgu_k: u8,
+    pub energy_store: u8,
+    pub control_electronics: u8,
+    pub turbocharger: u8,
+}
+
+impl EngineWear {
+    pub fn from_damage(damage: &CarDamageData) -> Self {
+        Self {
+            ice: damage.engine_ice_wear,
+            mgu_h: damage.engine_mgu_h_wear,
+            mgu_k: damage.engine_mgu_k_wear,
+            energy_store: damage.engine_es_wear,
+            control_electronics: damage.engine_ce_wear,
+            turbocharger: damage.engine_tc_wear,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeekendSession {
+    pub session_link_identifier: u32,
+    /// Backend ID of the session, once it has been created
+    pub session_uid: Option<String>,
+    pub session_type: Option<SessionType>,
+}
+
+/// Every session of a race weekend, and the state carried from one to the next.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Weekend {
+    /// Backend ID of the weekend, once it has been created
+    pub weekend_uid: Option<String>,
+    pub season_link_identifier: u32,
+    pub weekend_link_identifier: u32,
+    pub track_id: i8,
+    pub sessions: Vec<WeekendSession>,
+    /// Player's position at the end of qualifying
+    pub qualifying_position: Option<u8>,
+    /// Player's starting position in the race
+    pub grid_position: Option<u8>,
+    /// Latest power unit wear, carried into the next session
+    pub engine_wear: Option<EngineWear>,
+}
+
+impl Weekend {
+    pub fn new(link: WeekendLink, track_id: i8) -> Self {
+        Self {
+            weekend_uid: None,
+            season_link_identifier: link.season_link_identifier,
+            weekend_link_identifier: link.weekend_link_identifier,
+            track_id,
+            sessions: Vec::new(),
+            qualifying_position: None,
+            grid_position: None,
+            engine_wear: None,
+        }
+    }
+
+    /// Adds a session to the weekend. Returns false if it was already part of it.
+    pub fn add_session(&mut self, session_link_identifier: u32, session_type: Option<SessionType>) -> bool {
+        if self.sessions.iter().any(|s| s.session_link_identifier == session_link_identifier) {
+            return false;
+        }
+        self.sessions.push(WeekendSession { session_link_identifier, session_uid: None, session_type });
+        true
+    }
+
+    pub fn set_session_uid(&mut self, session_link_identifier: u32, session_uid: &str) {
+        if let Some(session) = self.sessions.iter_mut().find(|s| s.session_link_identifier == session_link_identifier) {
+            session.session_uid = Some(session_uid.to_string());
+        }
+    }
+
+    /// Keeps the player's qualifying and grid positions up to date.
+    pub fn record_position(&mut self, session_type: SessionType, lap_data: &LapData) {
+        if session_type.is_qualifying() {
+            self.qualifying_position = Some(lap_data.car_position);
+        } else if session_type.is_race() && lap_data.grid_position != 0 {
+            self.grid_position = Some(lap_data.grid_position);
+        }
+    }
+}
+
+/// Weekends that make up a season.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Championship {
+    pub season_link_identifier: u32,
+    pub weekend_link_identifiers: Vec<u32>,
+}
+
+impl Championship {
+    pub fn new(season_link_identifier: u32) -> Self {
+        Self { season_link_identifier, ..Default::default() }
+    }
+
+    /// Returns false if the weekend was already part of the championship.
+    pub fn add_weekend(&mut self, weekend_link_identifier: u32) -> bool {
+        if self.weekend_link_identifiers.contains(&weekend_link_identifier) {
+            return false;
+        }
+        self.weekend_link_identifiers.push(weekend_link_identifier);
+        true
+    }
+}